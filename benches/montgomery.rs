@@ -0,0 +1,90 @@
+// Compares the walk on the integers of the active backend with the walk on Montgomery
+// residues, and with the one on native integers where p fits into a u64.
+// Run with `cargo bench --bench montgomery`.
+#![cfg_attr(not(feature = "rug"), allow(clippy::useless_conversion))]
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use pollard_rho::group::MultiplicativeGroup;
+use pollard_rho::integer::{BigInteger, Integer, BACKEND};
+use pollard_rho::montgomery::{pollard_rho_montgomery, Context, Montgomery};
+use pollard_rho::small::SmallGroup;
+use pollard_rho::walk::Walk;
+use pollard_rho::{pollard_rho_in, CycleDetection, RhoConfig};
+
+/// Instances with the key 123456789: the safe prime p = 10^9 + 7 = 2q + 1, which fits into
+/// a u64, with 4 generating the subgroup of order q, and primes p = kq + 1 of 128 and
+/// 256 bits for q = 10^9 + 7, with 2^k generating the subgroup of order q.
+fn instances() -> Vec<(&'static str, Integer, Integer, Integer, Integer)> {
+	let key = Integer::from(123_456_789);
+	let n = Integer::from(1_000_000_007u64);
+	let safe = n.clone();
+	let mut instances = vec![("30-bit", Integer::from(4), safe, Integer::from(500_000_003u64))];
+	for (bits, k) in [
+		("128-bit", "170141182269480955845320612828"),
+		("256-bit", "57896044213385788218084974977749129082391088756082660727150166913910"),
+	] {
+		let k: Integer = k.parse().unwrap();
+		let p = Integer::from(&k * &n) + 1u32;
+		instances.push((bits, Integer::from(2).modpow(&k, &p), p, n.clone()));
+	}
+	instances
+		.into_iter()
+		.map(|(bits, base, p, n)| (bits, base.clone(), base.modpow(&key, &p), p, n))
+		.collect()
+}
+
+fn bench_walk(c: &mut Criterion) {
+	let seed = Integer::from(1);
+	for (bits, base, y, p, n) in instances() {
+		let big = MultiplicativeGroup::new(p.clone(), n.clone());
+		let small = SmallGroup::new(&p, &n);
+		let mut group = c.benchmark_group(format!("rho-{}", bits));
+		group.sample_size(10);
+		for walk in [Walk::Pollard, Walk::Adding { r: 20 }] {
+			let config = RhoConfig { cycle: CycleDetection::Brent, walk, ..Default::default() };
+			let name = format!("{:?}", walk);
+			group.bench_function(BenchmarkId::new(BACKEND, &name), |b| {
+				b.iter(|| pollard_rho_in(&big, &config, &seed, &base, &y))
+			});
+			group.bench_function(BenchmarkId::new("montgomery", &name), |b| {
+				b.iter(|| pollard_rho_montgomery(&config, &seed, &base, &y, &p, &n))
+			});
+			if let Some(small) = &small {
+				let (small_base, small_y) = (base.to_u64().unwrap(), y.to_u64().unwrap());
+				group.bench_function(BenchmarkId::new("native", &name), |b| {
+					b.iter(|| small.pollard_rho_report(&config, &seed, small_base, small_y))
+				});
+			}
+		}
+		group.finish();
+	}
+}
+
+fn bench_mul(c: &mut Criterion) {
+	for (bits, base, y, p, _) in instances() {
+		let mut group = c.benchmark_group(format!("mul-{}", bits));
+		group.bench_function(BACKEND, |b| {
+			b.iter(|| Integer::from(&base * &y).rem_euclid(&p))
+		});
+		match Montgomery::new(&p).unwrap() {
+			Montgomery::Limbs1(ctx) => bench_residues(&mut group, &ctx, &base, &y),
+			Montgomery::Limbs2(ctx) => bench_residues(&mut group, &ctx, &base, &y),
+			Montgomery::Limbs4(ctx) => bench_residues(&mut group, &ctx, &base, &y),
+			_ => unreachable!("the instances have at most 4 limbs"),
+		}
+		group.finish();
+	}
+}
+
+fn bench_residues<const N: usize>(
+	group: &mut BenchmarkGroup<WallTime>,
+	ctx: &Context<N>,
+	base: &Integer,
+	y: &Integer,
+) {
+	let (rb, ry) = (ctx.to_residue(base), ctx.to_residue(y));
+	group.bench_function("montgomery", |b| b.iter(|| ctx.mul(&rb, &ry)));
+}
+
+criterion_group!(benches, bench_walk, bench_mul);
+criterion_main!(benches);
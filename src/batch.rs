@@ -0,0 +1,218 @@
+// Discrete logs of many targets to the same base, sharing the distinguished points of the walks.
+// Source: F. Kuhn and R. Struik, "Random Walks Revisited: Extensions of Pollard's Rho Algorithm
+//         for Computing Multiple Discrete Logarithms", Selected Areas in Cryptography 2001.
+use crate::budget::Budget;
+use crate::check_order;
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::parallel::is_distinguished;
+use crate::utils::gen_bigint_range;
+use crate::validate::validate;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Configuration of the walks shared by all targets of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConfig {
+	/// Number of multipliers `base^m_j` of the r-adding walk.
+	pub r: usize,
+	/// A point is distinguished if the hash of it has at least this many trailing zero bits.
+	pub distinguished_bits: u32,
+	/// Cancellation token and limits, the iterations are the walk steps of the whole batch.
+	pub budget: Budget,
+}
+
+impl Default for BatchConfig {
+	fn default() -> Self {
+		BatchConfig { r: 20, distinguished_bits: 8, budget: Budget::default() }
+	}
+}
+
+impl BatchConfig {
+	/// Walks which do not reach a distinguished point after this many steps are
+	/// most likely trapped in a cycle and get abandoned.
+	fn max_walk_length(&self) -> u64 {
+		20u64 << self.distinguished_bits.min(40)
+	}
+}
+
+/// Computes the logs of all targets `y` with base**x mod p == y.
+/// The walk only multiplies by powers of `base`, so it does not depend on the target and the
+/// distinguished points reached from `y * base^a` have a known log once `y` is solved. Every
+/// target after the first is solved when one of its walks runs into such a point, thus each
+/// extra log gets cheaper.
+/// # Arguments
+/// * `config` - Walk and distinguished point criterion shared by all targets.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `ys` - Results of base**x mod p, solved in this order.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn batch_rho(
+	config: &BatchConfig,
+	seed: &Integer,
+	base: &Integer,
+	ys: &[Integer],
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<HashMap<Integer, Integer>> {
+	batch_rho_report(config, seed, base, ys, p, n).map(|(logs, _)| logs)
+}
+
+/// Same as [`batch_rho`] but also returns the statistics of each target in the order of `ys`.
+/// The walks of a target are not restarts, the iterations count the steps of all of them.
+pub fn batch_rho_report(
+	config: &BatchConfig,
+	seed: &Integer,
+	base: &Integer,
+	ys: &[Integer],
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(HashMap<Integer, Integer>, Vec<SolveReport>)> {
+	for y in ys {
+		validate(base, y, p, n)?;
+	}
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	batch_rho_report_in(&group, config, seed, base, ys)
+}
+
+/// Same as [`batch_rho_report`] in any [`Group`], the map is keyed by [`Group::encode`].
+pub fn batch_rho_report_in<G: Group>(
+	group: &G,
+	config: &BatchConfig,
+	seed: &Integer,
+	base: &G::Element,
+	ys: &[G::Element],
+) -> RhoResult<(HashMap<Integer, Integer>, Vec<SolveReport>)> {
+	let n = group.order();
+	check_order(n)?;
+	if config.r == 0 {
+		return Err(RhoError::InvalidParameters("batch walk needs at least one multiplier".into()))
+	}
+	let mut rand = seeded_rand(seed);
+	let zero = Integer::new();
+	let exponents: Vec<Integer> =
+		(0..config.r).map(|_| gen_bigint_range(&mut rand, &zero, n)).collect();
+	let multipliers: Vec<G::Element> = exponents.iter().map(|m| group.pow(base, m)).collect();
+	let partitions = config.r as u32;
+	let max_walk_length = config.max_walk_length();
+	// distinguished points base^log of known log, kept across the targets.
+	let mut known: HashMap<Integer, Integer> = HashMap::new();
+	let mut logs = HashMap::new();
+	let mut reports = Vec::with_capacity(ys.len());
+	let mut iterations = 0u64;
+	for (t, y) in ys.iter().enumerate() {
+		let target = group.encode(y);
+		if logs.contains_key(&target) {
+			reports.push(SolveReport::default());
+			continue
+		}
+		let start = Instant::now();
+		// distinguished points y * base^a of the walks of this target.
+		let mut wild: HashMap<Integer, Integer> = HashMap::new();
+		let mut steps = 0u64;
+		let mut walks = 0u64;
+		let x = loop {
+			if steps >= *n {
+				return Err(RhoError::IterationLimit)
+			}
+			// Without points of known log the first target also needs walks from base^a.
+			let tame = t == 0 && walks % 2 == 1;
+			walks += 1;
+			let mut a = gen_bigint_range(&mut rand, &zero, n);
+			let mut point = group.pow(base, &a);
+			if !tame {
+				point = group.op(y, &point);
+			}
+			let mut key = group.encode(&point);
+			let mut length = 0u64;
+			while length < max_walk_length && !is_distinguished(&key, config.distinguished_bits) {
+				config.budget.check(iterations).map_err(|reason| {
+					let report = SolveReport {
+						iterations: steps,
+						group_operations: steps,
+						elapsed: start.elapsed(),
+						..Default::default()
+					};
+					reason.into_error(report)
+				})?;
+				iterations += 1;
+				let j = key.mod_u(partitions) as usize;
+				point = group.op(&point, &multipliers[j]);
+				a = (a + &exponents[j]).rem_euclid(n);
+				key = group.encode(&point);
+				length += 1;
+			}
+			steps += length;
+			if length == max_walk_length {
+				continue
+			}
+			// y * base^a = base^log  ==>  x = log - a
+			match (tame, known.get(&key), wild.get(&key)) {
+				(false, Some(log), _) => break Integer::from(log - &a).rem_euclid(n),
+				(true, None, Some(a_wild)) => break Integer::from(&a - a_wild).rem_euclid(n),
+				(true, None, None) => {
+					known.insert(key, a);
+				},
+				(false, None, None) => {
+					wild.insert(key, a);
+				},
+				// the walk merged with an earlier one of the same kind.
+				_ => {},
+			}
+		};
+		for (key, a) in wild {
+			known.insert(key, Integer::from(&x + &a).rem_euclid(n));
+		}
+		logs.insert(target, x);
+		reports.push(SolveReport {
+			iterations: steps,
+			group_operations: steps,
+			elapsed: start.elapsed(),
+			..Default::default()
+		});
+	}
+	Ok((logs, reports))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_batch_rho() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		let config = BatchConfig { distinguished_bits: 2, ..Default::default() };
+		let keys: Vec<Integer> = [5, 77, 190, 0, 77, 123].map(Integer::from).to_vec();
+		let ys: Vec<Integer> = keys.iter().map(|key| two.modpow(key, &p)).collect();
+		let logs = batch_rho(&config, &Integer::from(1), &two, &ys, &p, &n).unwrap();
+		assert_eq!(logs.len(), 5);
+		for (y, key) in ys.iter().zip(&keys) {
+			assert_eq!(&logs[y], key);
+		}
+		let config = BatchConfig { r: 0, ..config };
+		let res = batch_rho(&config, &Integer::from(1), &two, &ys, &p, &n);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+
+	#[test]
+	fn test_batch_rho_gets_cheaper() {
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let four = Integer::from(4);
+		let config = BatchConfig { distinguished_bits: 4, ..Default::default() };
+		let keys: Vec<Integer> = (0..20u64).map(|i| Integer::from(i * 24_999_991 + 17)).collect();
+		let ys: Vec<Integer> = keys.iter().map(|key| four.modpow(key, &p)).collect();
+		let (logs, reports) =
+			batch_rho_report(&config, &Integer::from(7), &four, &ys, &p, &n).unwrap();
+		for (y, key) in ys.iter().zip(&keys) {
+			assert_eq!(&logs[y], key);
+		}
+		let last: u64 = reports[10..].iter().map(|report| report.iterations).sum();
+		assert!(last / 10 < reports[0].iterations, "{:?}", reports);
+		assert!(reports.iter().all(|report| report.restarts == 0));
+	}
+}
@@ -0,0 +1,162 @@
+// Shanks' baby-step giant-step algorithm with a bounded baby-step table.
+// Source: Handbook of Applied Cryptography, algorithm 3.56.
+use crate::check_order;
+use crate::budget::{Budget, StopReason};
+use crate::generic::{RhoError, RhoResult, SolveReport, ValidationError};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::integer::{BigInteger, Integer};
+use crate::small::SmallGroup;
+use crate::validate::validate;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Memory bound of the baby-step table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BsgsConfig {
+	/// Maximum number of baby steps stored in the table.
+	/// With `m` entries a DLP of order `n` takes up to `n/m` giant steps,
+	/// so the bound trades time for memory once it is below `sqrt(n)`.
+	pub max_table: u64,
+	/// Cancellation token and limits, every baby step and giant step is an iteration.
+	pub budget: Budget,
+}
+
+impl Default for BsgsConfig {
+	fn default() -> Self {
+		BsgsConfig { max_table: 1 << 20, budget: Budget::default() }
+	}
+}
+
+impl BsgsConfig {
+	/// Size `m = ceil(sqrt(n))` of the table which balances baby steps and giant steps.
+	pub fn optimal_table(n: &Integer) -> Integer {
+		let m = n.isqrt();
+		if Integer::from(&m * &m) == *n {
+			m
+		} else {
+			m + 1u32
+		}
+	}
+
+	/// Whether the balanced table for a group of order `n` fits into the memory bound.
+	pub fn fits(&self, n: &Integer) -> bool {
+		Self::optimal_table(n) <= self.max_table
+	}
+}
+
+/// Computes `x` = a mod n for the DLP base**x mod p == y, never fails for valid parameters.
+/// Runs on native integers whenever `p` fits into a u64.
+/// # Arguments
+/// * `config` - Memory bound of the baby-step table.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn bsgs(
+	config: &BsgsConfig,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	bsgs_report(config, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`bsgs`] but also returns the statistics, every baby step and giant step
+/// is an iteration.
+pub fn bsgs_report(
+	config: &BsgsConfig,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	if let Some(small) = SmallGroup::new(p, n) {
+		let (base, y) = (base.to_u64_wrapping(), y.to_u64_wrapping());
+		return small.bsgs_report(config, base, y).map(|(key, report)| (key.into(), report))
+	}
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	bsgs_report_in(&group, config, base, y)
+}
+
+/// Same as [`bsgs`] in any [`Group`] of order `n`.
+pub fn bsgs_in<G: Group>(
+	group: &G,
+	config: &BsgsConfig,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	bsgs_report_in(group, config, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`bsgs_in`] but also returns the statistics.
+pub fn bsgs_report_in<G: Group>(
+	group: &G,
+	config: &BsgsConfig,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)> {
+	let n = group.order();
+	check_order(n)?;
+	if config.max_table == 0 {
+		return Err(RhoError::InvalidParameters("BSGS table bound must be positive".into()))
+	}
+	let m = BsgsConfig::optimal_table(n).min(Integer::from(config.max_table));
+	// m <= max_table, so the table size fits into u64.
+	let steps = m.to_u64().unwrap();
+	let start = Instant::now();
+	let mut iterations = 0;
+	let report = |iterations: u64| SolveReport {
+		iterations,
+		group_operations: iterations,
+		elapsed: start.elapsed(),
+		..Default::default()
+	};
+	let stopped = |reason: StopReason, iterations: u64| reason.into_error(report(iterations));
+	let mut table = HashMap::new();
+	let mut baby = group.identity();
+	for j in 0..steps {
+		config.budget.check(iterations).map_err(|reason| stopped(reason, iterations))?;
+		iterations += 1;
+		table.entry(group.encode(&baby)).or_insert(j);
+		baby = group.op(&baby, base);
+	}
+	// giant = base^(-m) = base^(n - m mod n)
+	let giant = group.pow(base, &Integer::from(n - &m).rem_euclid(n));
+	let giant_steps = (Integer::from(n + &m) - 1u32) / &m;
+	let mut gamma = y.clone();
+	let mut i = Integer::new();
+	while i < giant_steps {
+		config.budget.check(iterations).map_err(|reason| stopped(reason, iterations))?;
+		iterations += 1;
+		if let Some(j) = table.get(&group.encode(&gamma)) {
+			return Ok(((Integer::from(&m * &i) + *j).rem_euclid(n), report(iterations)))
+		}
+		gamma = group.op(&gamma, &giant);
+		i += 1;
+	}
+	Err(ValidationError::TargetNotInSubgroup.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bsgs() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		// A table of 4 entries needs up to 48 giant steps but finds the same keys.
+		for config in [BsgsConfig::default(), BsgsConfig { max_table: 4, ..Default::default() }] {
+			for key in 0..191 {
+				let key = Integer::from(key);
+				let y = two.modpow(&key, &p);
+				assert_eq!(bsgs(&config, &two, &y, &p, &n), Ok(key));
+			}
+		}
+		let res = bsgs(&BsgsConfig { max_table: 0, ..Default::default() }, &two, &two, &p, &n);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+}
@@ -0,0 +1,147 @@
+// Cooperative cancellation and resource limits of the solver loops.
+use crate::generic::{RhoError, SolveReport};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The clock and the cancellation flag are only polled once in this many iterations.
+const CHECK_INTERVAL: u64 = 1 << 10;
+
+/// Flag to stop a running solver from another thread, clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Asks every solver holding this token to stop at its next check.
+	pub fn cancel(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+impl PartialEq for CancelToken {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+impl Eq for CancelToken {}
+
+/// Why a solver stopped before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+	/// The [`CancelToken`] was triggered.
+	Cancelled,
+	/// The wall-clock deadline passed.
+	TimedOut,
+	/// The iteration budget is used up.
+	IterationBudget,
+}
+
+/// Limits every solver loop checks, the default is unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Budget {
+	/// Stops the solver once cancelled.
+	pub token: Option<CancelToken>,
+	/// Stops the solver once this point in time has passed.
+	pub deadline: Option<Instant>,
+	/// Maximum number of iterations of the solver loop.
+	pub max_iterations: Option<u64>,
+}
+
+impl Budget {
+	pub fn with_token(mut self, token: CancelToken) -> Self {
+		self.token = Some(token);
+		self
+	}
+
+	/// Sets the deadline to `timeout` from now.
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.deadline = Some(Instant::now() + timeout);
+		self
+	}
+
+	pub fn with_max_iterations(mut self, max_iterations: u64) -> Self {
+		self.max_iterations = Some(max_iterations);
+		self
+	}
+
+	/// Whether the solver may start its next iteration after `iterations` iterations.
+	/// The iteration budget is exact, the clock and the token are polled periodically.
+	pub fn check(&self, iterations: u64) -> Result<(), StopReason> {
+		if self.max_iterations.is_some_and(|max| iterations >= max) {
+			return Err(StopReason::IterationBudget)
+		}
+		if !iterations.is_multiple_of(CHECK_INTERVAL) {
+			return Ok(())
+		}
+		if self.token.as_ref().is_some_and(CancelToken::is_cancelled) {
+			return Err(StopReason::Cancelled)
+		}
+		if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+			return Err(StopReason::TimedOut)
+		}
+		Ok(())
+	}
+
+	/// The budget left after `iterations` iterations, e.g. for the next restart.
+	pub(crate) fn spent(&self, iterations: u64) -> Budget {
+		Budget {
+			max_iterations: self.max_iterations.map(|max| max.saturating_sub(iterations)),
+			..self.clone()
+		}
+	}
+}
+
+impl StopReason {
+	/// The error reporting the statistics gathered until the solver stopped.
+	pub(crate) fn into_error(self, report: SolveReport) -> RhoError {
+		RhoError::Cancelled(self, Box::new(report))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bsgs::{bsgs, BsgsConfig};
+	use crate::{try_pollard_rho_report, CycleDetection, RhoConfig};
+	use crate::integer::{BigInteger, Integer};
+
+	#[test]
+	fn test_budget() {
+		// 10^9 + 7 = 2 * 500000003 + 1, the walk needs far more than 100 iterations.
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let four = Integer::from(4);
+		let y = four.modpow(&Integer::from(123_456_789), &p);
+		let seed = Integer::new();
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let budget = Budget::default().with_max_iterations(100);
+			let config = RhoConfig { cycle, budget, ..Default::default() };
+			match try_pollard_rho_report(&config, 10, &seed, &four, &y, &p, &n) {
+				Err(RhoError::Cancelled(StopReason::IterationBudget, report)) => {
+					assert_eq!(report.iterations, 100);
+					assert_eq!(report.restarts, 0);
+				},
+				res => panic!("unexpected result {:?}", res),
+			}
+		}
+		let token = CancelToken::new();
+		token.cancel();
+		let config =
+			RhoConfig { budget: Budget::default().with_token(token), ..Default::default() };
+		let res = try_pollard_rho_report(&config, 10, &seed, &four, &y, &p, &n);
+		assert!(matches!(res, Err(RhoError::Cancelled(StopReason::Cancelled, _))));
+		let budget = Budget::default().with_timeout(Duration::ZERO);
+		let config = BsgsConfig { budget, ..Default::default() };
+		let res = bsgs(&config, &four, &y, &p, &n);
+		assert!(matches!(res, Err(RhoError::Cancelled(StopReason::TimedOut, _))));
+	}
+}
@@ -0,0 +1,454 @@
+// Resumable snapshots of the rho walk.
+// Every walk point satisfies x = base^a * y^b, so the exponents (a, b) of the two points
+// the cycle detection compares are enough to restore the walk in any group, and the
+// iteration function itself is rebuilt from the seed. The encodings of base, y and the first
+// point of the walk tie a snapshot to its DLP instance.
+use crate::generic::{RhoError, RhoResult};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::integer::Integer;
+use crate::observer::NoObserver;
+use crate::validate::validate;
+use crate::walk::{Escapes, Walk, Walker};
+use crate::{rho_attempt, CycleDetection, RhoConfig};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Version of the on-disk checkpoint format written by [`WalkState::save`].
+pub const FORMAT_VERSION: u32 = 4;
+/// First word of every checkpoint file.
+const MAGIC: &str = "pollard_rho-checkpoint";
+
+/// State of the cycle detection loop after `iteration` iterations.
+/// For Floyd's algorithm (a_j, b_j) belong to the point x_2i, for Brent's algorithm to
+/// the saved point, which is compared with the walk for `power` steps and has been for `lam`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkState {
+	/// Seed the walk was started from.
+	pub seed: Integer,
+	/// Order of the group the walk runs in.
+	pub order: Integer,
+	/// Encoding of the generator.
+	pub base: Integer,
+	/// Encoding of the element whose logarithm is searched.
+	pub y: Integer,
+	/// Encoding of the first point of the walk, which also tells apart groups with the same
+	/// order and encodings of base and y, e.g. two moduli.
+	pub origin: Integer,
+	/// Iteration function of the walk.
+	pub walk: Walk,
+	/// Cycle detection algorithm.
+	pub cycle: CycleDetection,
+	/// Whether the walk runs on the classes of the negation map.
+	pub negation_map: bool,
+	/// Number of completed iterations.
+	pub iteration: u64,
+	pub a_i: Integer,
+	pub b_i: Integer,
+	pub a_j: Integer,
+	pub b_j: Integer,
+	pub power: u64,
+	pub lam: u64,
+	/// Fruitless cycles of the negation map the walk has escaped from.
+	pub escapes: Escapes,
+}
+
+impl WalkState {
+	/// State before the first iteration of a walk from base^a * y^b.
+	pub(crate) fn start<G: Group>(
+		walker: &Walker<G>,
+		config: &RhoConfig,
+		seed: &Integer,
+		a: Integer,
+		b: Integer,
+	) -> Self {
+		let group = walker.group();
+		WalkState {
+			seed: seed.clone(),
+			order: group.order().clone(),
+			base: group.encode(walker.base()),
+			y: group.encode(walker.y()),
+			origin: group.encode(&walker.start(&a, &b)),
+			walk: config.walk,
+			cycle: config.cycle,
+			negation_map: config.negation_map,
+			iteration: 0,
+			a_j: a.clone(),
+			b_j: b.clone(),
+			a_i: a,
+			b_i: b,
+			power: 1,
+			lam: 0,
+			escapes: Escapes::default(),
+		}
+	}
+
+	/// Whether both states belong to the same walk, i.e. the same settings, seed and instance.
+	pub fn matches(&self, other: &WalkState) -> bool {
+		self.walk == other.walk
+			&& self.cycle == other.cycle
+			&& self.negation_map == other.negation_map
+			&& self.seed == other.seed
+			&& self.order == other.order
+			&& self.base == other.base
+			&& self.y == other.y
+			&& self.origin == other.origin
+	}
+
+	/// Writes the state to `path`, replacing the previous snapshot only once the new one is complete.
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let mut tmp = path.as_os_str().to_owned();
+		tmp.push(".tmp");
+		fs::write(&tmp, self.to_string())?;
+		fs::rename(&tmp, path)
+	}
+
+	/// Reads a state written by [`WalkState::save`].
+	pub fn load(path: &Path) -> io::Result<Self> {
+		fs::read_to_string(path)?.parse()
+	}
+}
+
+impl fmt::Display for WalkState {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "{} {}", MAGIC, FORMAT_VERSION)?;
+		match self.walk {
+			Walk::Pollard => writeln!(f, "walk pollard")?,
+			Walk::Adding { r } => writeln!(f, "walk adding {}", r)?,
+			Walk::Mixed { r, squarings } => writeln!(f, "walk mixed {} {}", r, squarings)?,
+		}
+		match self.cycle {
+			CycleDetection::Floyd => writeln!(f, "cycle floyd")?,
+			CycleDetection::Brent => writeln!(f, "cycle brent")?,
+		}
+		writeln!(f, "negation_map {}", self.negation_map)?;
+		writeln!(f, "seed {}", self.seed)?;
+		writeln!(f, "order {}", self.order)?;
+		writeln!(f, "base {}", self.base)?;
+		writeln!(f, "y {}", self.y)?;
+		writeln!(f, "origin {}", self.origin)?;
+		writeln!(f, "iteration {}", self.iteration)?;
+		writeln!(f, "a_i {}", self.a_i)?;
+		writeln!(f, "b_i {}", self.b_i)?;
+		writeln!(f, "a_j {}", self.a_j)?;
+		writeln!(f, "b_j {}", self.b_j)?;
+		writeln!(f, "power {}", self.power)?;
+		writeln!(f, "lam {}", self.lam)?;
+		for (key, a, b) in self.escapes.iter() {
+			writeln!(f, "escape {} {} {}", key, a, b)?;
+		}
+		Ok(())
+	}
+}
+
+fn invalid(reason: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+impl FromStr for WalkState {
+	type Err = io::Error;
+
+	fn from_str(s: &str) -> io::Result<Self> {
+		let mut lines = s.lines();
+		let header = lines.next().unwrap_or_default();
+		// older versions do not identify the instance or keep the escaped cycles, so they
+		// cannot be resumed.
+		match header.split_once(' ') {
+			Some((MAGIC, version)) => match version.parse::<u32>() {
+				Ok(FORMAT_VERSION) => (),
+				_ => return Err(invalid(format!("unsupported checkpoint version {}", version))),
+			},
+			_ => return Err(invalid("not a checkpoint file".into())),
+		}
+		let fields: HashMap<&str, &str> = lines.filter_map(|line| line.split_once(' ')).collect();
+		let field =
+			|key: &str| fields.get(key).copied().ok_or_else(|| invalid(format!("missing {}", key)));
+		let integer = |key: &str| {
+			field(key)?.parse::<Integer>().map_err(|_| invalid(format!("invalid {}", key)))
+		};
+		let number =
+			|key: &str| field(key)?.parse::<u64>().map_err(|_| invalid(format!("invalid {}", key)));
+		let usize_at = |words: &[&str], i: usize| {
+			words
+				.get(i)
+				.and_then(|w| w.parse::<usize>().ok())
+				.ok_or_else(|| invalid("invalid walk".into()))
+		};
+		let words: Vec<&str> = field("walk")?.split(' ').collect();
+		let walk = match words[0] {
+			"pollard" => Walk::Pollard,
+			"adding" => Walk::Adding { r: usize_at(&words, 1)? },
+			"mixed" => Walk::Mixed { r: usize_at(&words, 1)?, squarings: usize_at(&words, 2)? },
+			other => return Err(invalid(format!("unknown walk {}", other))),
+		};
+		let cycle = match field("cycle")? {
+			"floyd" => CycleDetection::Floyd,
+			"brent" => CycleDetection::Brent,
+			other => return Err(invalid(format!("unknown cycle detection {}", other))),
+		};
+		let escapes = s
+			.lines()
+			.filter_map(|line| line.strip_prefix("escape "))
+			.map(|line| {
+				let mut words = line.split(' ').map(|word| word.parse::<Integer>().ok());
+				match (words.next(), words.next(), words.next(), words.next()) {
+					(Some(Some(key)), Some(Some(a)), Some(Some(b)), None) => Ok((key, a, b)),
+					_ => Err(invalid("invalid escape".into())),
+				}
+			})
+			.collect::<io::Result<Escapes>>()?;
+		let negation_map = field("negation_map")?
+			.parse::<bool>()
+			.map_err(|_| invalid("invalid negation_map".into()))?;
+		Ok(WalkState {
+			seed: integer("seed")?,
+			order: integer("order")?,
+			base: integer("base")?,
+			y: integer("y")?,
+			origin: integer("origin")?,
+			walk,
+			cycle,
+			negation_map,
+			iteration: number("iteration")?,
+			a_i: integer("a_i")?,
+			b_i: integer("b_i")?,
+			a_j: integer("a_j")?,
+			b_j: integer("b_j")?,
+			power: number("power")?,
+			lam: number("lam")?,
+			escapes,
+		})
+	}
+}
+
+/// Receives the snapshots of a walk.
+pub type Sink<'a> = &'a mut dyn FnMut(&WalkState) -> io::Result<()>;
+
+/// How often the cycle detection loops hand their state to the sink.
+pub(crate) struct Checkpoints<'a> {
+	every: u64,
+	sink: Option<Sink<'a>>,
+}
+
+impl<'a> Checkpoints<'a> {
+	pub(crate) fn new(every: u64, sink: Sink<'a>) -> Self {
+		Checkpoints { every, sink: Some(sink) }
+	}
+
+	pub(crate) fn disabled() -> Self {
+		Checkpoints { every: 0, sink: None }
+	}
+
+	/// Passes the state on to the sink every `every` iterations.
+	pub(crate) fn offer(&mut self, state: &WalkState) -> RhoResult<()> {
+		match &mut self.sink {
+			Some(sink) if self.every != 0 && state.iteration.is_multiple_of(self.every) =>
+				sink(state).map_err(|err| RhoError::Checkpoint(err.to_string())),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Same as [`crate::pollard_rho_in`] but hands a snapshot of the walk to `sink` every
+/// `every` iterations and optionally continues from such a snapshot.
+/// A resumed walk performs exactly the remaining steps of the original one,
+/// so it returns the same result as an uninterrupted run.
+/// # Arguments
+/// * `group` - Group over which DLP is generated.
+/// * `config` - Cycle detection algorithm and iteration function of the walk.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x.
+/// * `every` - Snapshot interval in iterations, `0` disables the snapshots.
+/// * `resume` - Snapshot of a walk with the same settings and instance to continue from.
+/// * `sink` - Receives the snapshots, e.g. writes them with [`WalkState::save`].
+#[allow(clippy::too_many_arguments)]
+pub fn pollard_rho_checkpointed_in<G: Group>(
+	group: &G,
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	every: u64,
+	resume: Option<&WalkState>,
+	sink: Sink,
+) -> RhoResult<Integer> {
+	let mut checkpoints = Checkpoints::new(every, sink);
+	rho_attempt(group, config, seed, base, y, resume, &mut checkpoints, &mut NoObserver).0
+}
+
+/// Solves the DLP base**x mod p == y with a single rho walk which is saved to `path` every
+/// `every` iterations. If `path` already holds a snapshot of the same walk, it is resumed.
+/// # Arguments
+/// * `config` - Cycle detection algorithm and iteration function of the walk.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+/// * `path` - Checkpoint file.
+/// * `every` - Snapshot interval in iterations.
+#[allow(clippy::too_many_arguments)]
+pub fn pollard_rho_resumable(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+	path: &Path,
+	every: u64,
+) -> RhoResult<Integer> {
+	validate(base, y, p, n)?;
+	let resume = match WalkState::load(path) {
+		Ok(state) => Some(state),
+		Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+		Err(err) => return Err(RhoError::Checkpoint(err.to_string())),
+	};
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	let mut save = |state: &WalkState| state.save(path);
+	pollard_rho_checkpointed_in(&group, config, seed, base, y, every, resume.as_ref(), &mut save)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ec::{Curve, CurveGroup, Point};
+	use crate::integer::BigInteger;
+
+	#[test]
+	fn test_resume() {
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let four = Integer::from(4);
+		let y = four.modpow(&Integer::from(123_456_789), &p);
+		let group = MultiplicativeGroup::new(p.clone(), n.clone());
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let config = RhoConfig { cycle, walk: Walk::Adding { r: 20 }, ..Default::default() };
+			let seed = Integer::from(3);
+			let mut snapshots = Vec::new();
+			let mut keep = |state: &WalkState| {
+				snapshots.push(state.clone());
+				Ok(())
+			};
+			let res = pollard_rho_checkpointed_in(
+				&group, &config, &seed, &four, &y, 1000, None, &mut keep,
+			);
+			assert!(snapshots.len() > 2);
+			// every snapshot survives the file format and resumes to the same result.
+			for state in snapshots.iter().step_by(snapshots.len() / 3) {
+				let restored: WalkState = state.to_string().parse().unwrap();
+				assert_eq!(&restored, state);
+				let mut ignore = |_: &WalkState| Ok(());
+				let resumed = pollard_rho_checkpointed_in(
+					&group,
+					&config,
+					&seed,
+					&four,
+					&y,
+					0,
+					Some(&restored),
+					&mut ignore,
+				);
+				assert_eq!(resumed, res);
+			}
+			let other = Integer::from(4);
+			let mut ignore = |_: &WalkState| Ok(());
+			let res = pollard_rho_checkpointed_in(
+				&group,
+				&config,
+				&other,
+				&four,
+				&y,
+				0,
+				snapshots.first(),
+				&mut ignore,
+			);
+			assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+			// resume through a checkpoint file.
+			let path =
+				std::env::temp_dir().join(format!("pollard_rho_{}.ckpt", std::process::id()));
+			snapshots[snapshots.len() / 2].save(&path).unwrap();
+			let resumed = pollard_rho_resumable(&config, &seed, &four, &y, &p, &n, &path, 1000);
+			assert_eq!(resumed, Ok(Integer::from(123_456_789)));
+			fs::remove_file(&path).unwrap();
+			// a snapshot of another instance with the same order and seed is refused.
+			let modulus = MultiplicativeGroup::new(Integer::from(1_000_000_009u64), n.clone());
+			let y_other = four.modpow(&Integer::from(123_456_790), &p);
+			for (group, y) in [(&modulus, &y), (&group, &y_other)] {
+				let res = pollard_rho_checkpointed_in(
+					group,
+					&config,
+					&seed,
+					&four,
+					y,
+					0,
+					snapshots.first(),
+					&mut ignore,
+				);
+				assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+			}
+		}
+		// versions other than the current one are refused, older ones lack the instance.
+		for header in ["pollard_rho-checkpoint 3\n", "pollard_rho-checkpoint 5\n"] {
+			let err = header.parse::<WalkState>().unwrap_err();
+			assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		}
+	}
+
+	#[test]
+	fn test_resume_negation_map() {
+		// y^2 = x^3 + x + 5 over F_100003 has prime order 99707.
+		let curve =
+			Curve::new(Integer::from(1), Integer::from(5), Integer::from(100_003)).unwrap();
+		let g = Point::Affine(Integer::from(2), Integer::from(86_328));
+		let group = CurveGroup::new(curve.clone(), Integer::from(99_707));
+		let key = Integer::from(31_337);
+		let q = curve.mul(&g, &key);
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let walk = Walk::Adding { r: 4 };
+			let config = RhoConfig { cycle, walk, negation_map: true, ..Default::default() };
+			let mut escaped = 0;
+			for seed in 0..5 {
+				let seed = Integer::from(seed);
+				let mut snapshots = Vec::new();
+				let mut keep = |state: &WalkState| {
+					snapshots.push(state.clone());
+					Ok(())
+				};
+				let res = pollard_rho_checkpointed_in(
+					&group, &config, &seed, &g, &q, 10, None, &mut keep,
+				);
+				let Some(k) = snapshots.iter().position(|state| !state.escapes.is_empty()) else {
+					continue
+				};
+				// the escaped cycles survive the file format, and the resumed walk goes through
+				// exactly the same states as the uninterrupted one.
+				let restored: WalkState = snapshots[k].to_string().parse().unwrap();
+				assert_eq!(restored, snapshots[k]);
+				let mut resumed_snapshots = Vec::new();
+				let mut keep = |state: &WalkState| {
+					resumed_snapshots.push(state.clone());
+					Ok(())
+				};
+				let resumed = pollard_rho_checkpointed_in(
+					&group,
+					&config,
+					&seed,
+					&g,
+					&q,
+					10,
+					Some(&restored),
+					&mut keep,
+				);
+				assert_eq!(resumed, res);
+				assert_eq!(resumed_snapshots, snapshots[k + 1..]);
+				escaped += 1;
+			}
+			assert!(escaped > 0);
+		}
+	}
+}
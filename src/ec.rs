@@ -0,0 +1,434 @@
+// Elliptic curve discrete logarithm over prime fields.
+// Source: Guide to Elliptic Curve Cryptography (Hankerson, Menezes, Vanstone), chapter 3.
+use crate::generic::{RhoError, RhoResult};
+use crate::group::Group;
+use crate::integer::{BigInteger, Integer};
+use crate::{pollard_rho_in, RhoConfig};
+
+/// Number of Miller-Rabin rounds used for the primality checks.
+const PRIME_REPS: u32 = 30;
+
+/// Non-singular short Weierstrass curve y^2 = x^3 + a*x + b over the prime field F_p, p > 3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curve {
+	a: Integer,
+	b: Integer,
+	p: Integer,
+}
+
+/// Point of a curve in affine coordinates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Point {
+	/// The point at infinity, neutral element of the curve group.
+	Infinity,
+	/// The point (x, y) with both coordinates reduced modulo p.
+	Affine(Integer, Integer),
+}
+
+/// Point of a curve in Jacobian projective coordinates (X : Y : Z),
+/// standing for the affine point (X/Z^2, Y/Z^3). `Z == 0` is the point at infinity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectivePoint {
+	pub x: Integer,
+	pub y: Integer,
+	pub z: Integer,
+}
+
+impl Curve {
+	/// Fails unless `p` is a prime above 3 and the discriminant 4a^3 + 27b^2 is non-zero,
+	/// so every field element the point arithmetic divides by is invertible.
+	pub fn new(a: Integer, b: Integer, p: Integer) -> RhoResult<Self> {
+		if p <= 3 || !p.is_probable_prime(PRIME_REPS) {
+			return Err(RhoError::InvalidParameters(format!("{} is not a prime above 3", p)))
+		}
+		let (a, b) = (a.rem_euclid(&p), b.rem_euclid(&p));
+		let discriminant = Integer::from(&a * &a) * &a * 4u32 + Integer::from(&b * &b) * 27u32;
+		if discriminant.is_divisible(&p) {
+			return Err(RhoError::InvalidParameters("the curve is singular".into()))
+		}
+		Ok(Curve { a, b, p })
+	}
+
+	pub fn a(&self) -> &Integer {
+		&self.a
+	}
+
+	pub fn b(&self) -> &Integer {
+		&self.b
+	}
+
+	pub fn p(&self) -> &Integer {
+		&self.p
+	}
+
+	fn reduce(&self, v: Integer) -> Integer {
+		v.rem_euclid(&self.p)
+	}
+
+	/// Inverse of a field element which is non-zero modulo the prime `p`.
+	fn inverse(&self, v: &Integer) -> Integer {
+		v.modinv(&self.p).expect("Non-zero elements of a prime field should be invertible!")
+	}
+
+	/// Whether `point` satisfies the curve equation.
+	pub fn contains(&self, point: &Point) -> bool {
+		match point {
+			Point::Infinity => true,
+			Point::Affine(x, y) => {
+				let lhs = self.reduce(Integer::from(y * y));
+				let x3 = Integer::from(x * x) * x;
+				let rhs = self.reduce(x3 + Integer::from(&self.a * x) + &self.b);
+				lhs == rhs
+			},
+		}
+	}
+
+	/// Negation -P = (x, -y).
+	pub fn neg(&self, point: &Point) -> Point {
+		match point {
+			Point::Infinity => Point::Infinity,
+			Point::Affine(x, y) => Point::Affine(x.clone(), self.reduce(Integer::from(-y))),
+		}
+	}
+
+	/// Affine point addition P + Q.
+	pub fn add(&self, lhs: &Point, rhs: &Point) -> Point {
+		let ((x1, y1), (x2, y2)) = match (lhs, rhs) {
+			(Point::Infinity, _) => return rhs.clone(),
+			(_, Point::Infinity) => return lhs.clone(),
+			(Point::Affine(x1, y1), Point::Affine(x2, y2)) => ((x1, y1), (x2, y2)),
+		};
+		let den = self.reduce(Integer::from(x2 - x1));
+		if den == 0 {
+			if self.reduce(Integer::from(y1 + y2)) == 0 {
+				return Point::Infinity
+			}
+			return self.double(lhs)
+		}
+		let num = Integer::from(y2 - y1);
+		let lambda = self.reduce(num * self.inverse(&den));
+		let x3 = self.reduce(Integer::from(&lambda * &lambda) - x1 - x2);
+		let y3 = self.reduce(lambda * Integer::from(x1 - &x3) - y1);
+		Point::Affine(x3, y3)
+	}
+
+	/// Affine point doubling 2P.
+	pub fn double(&self, point: &Point) -> Point {
+		let (x, y) = match point {
+			Point::Infinity => return Point::Infinity,
+			Point::Affine(x, y) => (x, y),
+		};
+		let den = self.reduce(Integer::from(y * 2));
+		if den == 0 {
+			return Point::Infinity
+		}
+		let num = Integer::from(x * x) * 3 + &self.a;
+		let lambda = self.reduce(num * self.inverse(&den));
+		let x3 = self.reduce(Integer::from(&lambda * &lambda) - Integer::from(x * 2));
+		let y3 = self.reduce(lambda * Integer::from(x - &x3) - y);
+		Point::Affine(x3, y3)
+	}
+
+	/// Scalar multiplication k*P, computed in projective coordinates.
+	pub fn mul(&self, point: &Point, k: &Integer) -> Point {
+		let point = if *k < 0 { self.neg(point) } else { point.clone() };
+		let k = k.abs_value();
+		self.to_affine(&self.mul_projective(&self.to_projective(&point), &k))
+	}
+
+	/// Convert an affine point to Jacobian coordinates.
+	pub fn to_projective(&self, point: &Point) -> ProjectivePoint {
+		match point {
+			Point::Infinity =>
+				ProjectivePoint { x: Integer::from(1), y: Integer::from(1), z: Integer::new() },
+			Point::Affine(x, y) =>
+				ProjectivePoint { x: x.clone(), y: y.clone(), z: Integer::from(1) },
+		}
+	}
+
+	/// Convert a Jacobian point back to affine coordinates, costs one field inversion.
+	pub fn to_affine(&self, point: &ProjectivePoint) -> Point {
+		if self.reduce(point.z.clone()) == 0 {
+			return Point::Infinity
+		}
+		let z_inv = self.inverse(&point.z);
+		let z_inv2 = self.reduce(Integer::from(&z_inv * &z_inv));
+		let x = self.reduce(Integer::from(&point.x * &z_inv2));
+		let y = self.reduce(Integer::from(&point.y * &z_inv2) * &z_inv);
+		Point::Affine(x, y)
+	}
+
+	/// Jacobian point doubling, "dbl-2007-bl" without field inversion.
+	pub fn double_projective(&self, point: &ProjectivePoint) -> ProjectivePoint {
+		if point.z == 0 || point.y == 0 {
+			return self.to_projective(&Point::Infinity)
+		}
+		let (x, y, z) = (&point.x, &point.y, &point.z);
+		let xx = self.reduce(Integer::from(x * x));
+		let yy = self.reduce(Integer::from(y * y));
+		let yyyy = self.reduce(Integer::from(&yy * &yy));
+		let zz = self.reduce(Integer::from(z * z));
+		let s = self.reduce(Integer::from(x * &yy) * 4);
+		let m = self.reduce(xx * 3 + Integer::from(&zz * &zz) * &self.a);
+		let x3 = self.reduce(Integer::from(&m * &m) - Integer::from(&s * 2));
+		let y3 = self.reduce(m * Integer::from(&s - &x3) - yyyy * 8);
+		let z3 = self.reduce(Integer::from(y * z) * 2);
+		ProjectivePoint { x: x3, y: y3, z: z3 }
+	}
+
+	/// Jacobian point addition, "add-2007-bl" without field inversion.
+	pub fn add_projective(&self, lhs: &ProjectivePoint, rhs: &ProjectivePoint) -> ProjectivePoint {
+		if lhs.z == 0 {
+			return rhs.clone()
+		}
+		if rhs.z == 0 {
+			return lhs.clone()
+		}
+		let z1z1 = self.reduce(Integer::from(&lhs.z * &lhs.z));
+		let z2z2 = self.reduce(Integer::from(&rhs.z * &rhs.z));
+		let u1 = self.reduce(Integer::from(&lhs.x * &z2z2));
+		let u2 = self.reduce(Integer::from(&rhs.x * &z1z1));
+		let s1 = self.reduce(Integer::from(&lhs.y * &rhs.z) * &z2z2);
+		let s2 = self.reduce(Integer::from(&rhs.y * &lhs.z) * &z1z1);
+		let h = self.reduce(Integer::from(&u2 - &u1));
+		let r = self.reduce(Integer::from(&s2 - &s1));
+		if h == 0 {
+			if r == 0 {
+				return self.double_projective(lhs)
+			}
+			return self.to_projective(&Point::Infinity)
+		}
+		let hh = self.reduce(Integer::from(&h * &h));
+		let hhh = self.reduce(Integer::from(&h * &hh));
+		let v = self.reduce(u1 * &hh);
+		let x3 = self.reduce(Integer::from(&r * &r) - &hhh - Integer::from(&v * 2));
+		let y3 = self.reduce(r * Integer::from(&v - &x3) - s1 * hhh);
+		let z3 = self.reduce(Integer::from(&lhs.z * &rhs.z) * h);
+		ProjectivePoint { x: x3, y: y3, z: z3 }
+	}
+
+	/// Double-and-add scalar multiplication in Jacobian coordinates, `k` must be non-negative.
+	pub fn mul_projective(&self, point: &ProjectivePoint, k: &Integer) -> ProjectivePoint {
+		let mut res = self.to_projective(&Point::Infinity);
+		for i in (0..k.significant_bits()).rev() {
+			res = self.double_projective(&res);
+			if k.get_bit(i) {
+				res = self.add_projective(&res, point);
+			}
+		}
+		res
+	}
+}
+
+/// The cyclic subgroup of order `n` of a curve, written additively:
+/// the group operation is point addition and exponentiation is scalar multiplication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurveGroup {
+	curve: Curve,
+	n: Integer,
+}
+
+impl CurveGroup {
+	/// # Arguments
+	/// * `curve` - Curve the points lie on.
+	/// * `n` - Order of the subgroup generated by the base point.
+	pub fn new(curve: Curve, n: Integer) -> Self {
+		CurveGroup { curve, n }
+	}
+
+	pub fn curve(&self) -> &Curve {
+		&self.curve
+	}
+}
+
+impl Group for CurveGroup {
+	type Element = Point;
+
+	fn identity(&self) -> Point {
+		Point::Infinity
+	}
+
+	fn op(&self, a: &Point, b: &Point) -> Point {
+		self.curve.add(a, b)
+	}
+
+	fn pow(&self, a: &Point, k: &Integer) -> Point {
+		self.curve.mul(a, k)
+	}
+
+	fn equal(&self, a: &Point, b: &Point) -> bool {
+		a == b
+	}
+
+	/// Compressed encoding `2x + (y mod 2) + 1`, the point at infinity is encoded as 0.
+	fn encode(&self, a: &Point) -> Integer {
+		match a {
+			Point::Infinity => Integer::new(),
+			Point::Affine(x, y) => Integer::from(x * 2) + y.mod_u(2) + 1,
+		}
+	}
+
+	fn order(&self) -> &Integer {
+		&self.n
+	}
+
+	fn inverse(&self, a: &Point) -> Option<Point> {
+		Some(self.curve.neg(a))
+	}
+}
+
+/// Check every precondition of the ECDLP `q = k*point` in the subgroup of prime order `n`
+/// and report the first one which fails, the curve equivalent of [`crate::validate::validate`].
+/// # Arguments
+/// * `curve` - Curve over which ECDLP is generated.
+/// * `n` - Order of `point`.
+/// * `point` - Base point of the subgroup.
+/// * `q` - Result of k*point.
+pub fn validate(curve: &Curve, n: &Integer, point: &Point, q: &Point) -> RhoResult<()> {
+	let invalid = |reason: &str| Err(RhoError::InvalidParameters(reason.into()));
+	let reduced = |point: &Point| match point {
+		Point::Infinity => true,
+		Point::Affine(x, y) => *x >= 0 && *x < curve.p && *y >= 0 && *y < curve.p,
+	};
+	if !n.is_probable_prime(PRIME_REPS) {
+		return invalid("the order n is not prime")
+	}
+	if *point == Point::Infinity || !reduced(point) || !curve.contains(point) {
+		return invalid("the base point is not a finite point of the curve")
+	}
+	if !reduced(q) || !curve.contains(q) {
+		return invalid("q is not a point of the curve")
+	}
+	if curve.mul(point, n) != Point::Infinity {
+		return invalid("the base point does not have order n")
+	}
+	if curve.mul(q, n) != Point::Infinity {
+		return invalid("q is not in the subgroup generated by the base point")
+	}
+	Ok(())
+}
+
+/// Solves the ECDLP `q = k*point` with the pollard rho walk.
+/// # Arguments
+/// * `curve` - Curve over which ECDLP is generated.
+/// * `n` - Order of `point`, must be prime.
+/// * `config` - Cycle detection algorithm and iteration function of the walk,
+///   [`RhoConfig::negation_map`] walks on the classes {P, -P}.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `point` - Base point of the subgroup.
+/// * `q` - Result of k*point.
+pub fn ecdlp_rho(
+	curve: &Curve,
+	n: &Integer,
+	config: &RhoConfig,
+	seed: &Integer,
+	point: &Point,
+	q: &Point,
+) -> RhoResult<Integer> {
+	validate(curve, n, point, q)?;
+	let group = CurveGroup::new(curve.clone(), n.clone());
+	pollard_rho_in(&group, config, seed, point, q)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::generic::RhoError;
+	use crate::group::MultiplicativeGroup;
+	use crate::walk::Walk;
+	use crate::{try_pollard_rho_report_in, CycleDetection};
+
+	// y^2 = x^3 + 2x + 2 over F_17, the point (5, 1) generates a group of order 19.
+	fn toy_curve() -> (Curve, Point, Integer) {
+		let curve = Curve::new(Integer::from(2), Integer::from(2), Integer::from(17)).unwrap();
+		(curve, Point::Affine(Integer::from(5), Integer::from(1)), Integer::from(19))
+	}
+
+	#[test]
+	fn test_point_arithmetic() {
+		let (curve, g, n) = toy_curve();
+		let mut acc = Point::Infinity;
+		for k in 0..19 {
+			assert!(curve.contains(&acc));
+			assert_eq!(curve.mul(&g, &Integer::from(k)), acc, "k = {}", k);
+			acc = curve.add(&acc, &g);
+		}
+		assert_eq!(acc, Point::Infinity);
+		assert_eq!(curve.mul(&g, &n), Point::Infinity);
+		assert_eq!(curve.add(&g, &curve.neg(&g)), Point::Infinity);
+		assert_eq!(curve.double(&g), Point::Affine(Integer::from(6), Integer::from(3)));
+	}
+
+	#[test]
+	fn test_ecdlp_rho() {
+		let (curve, g, n) = toy_curve();
+		let config = RhoConfig { walk: Walk::Adding { r: 8 }, ..Default::default() };
+		for k in 1..19 {
+			let q = curve.mul(&g, &Integer::from(k));
+			let key = (0..20)
+				.find_map(|seed| ecdlp_rho(&curve, &n, &config, &Integer::from(seed), &g, &q).ok())
+				.expect("Pollard rho should find the key!");
+			assert_eq!(key, k);
+		}
+	}
+
+	#[test]
+	fn test_invalid_curve() {
+		let invalid = |err: Option<RhoError>| matches!(err, Some(RhoError::InvalidParameters(_)));
+		assert!(invalid(Curve::new(Integer::from(2), Integer::from(2), Integer::from(15)).err()));
+		assert!(invalid(Curve::new(Integer::from(2), Integer::from(2), Integer::from(3)).err()));
+		// 4 * (-3)^3 + 27 * 2^2 == 0, the curve has a node at (1, 0).
+		assert!(invalid(Curve::new(Integer::from(-3), Integer::from(2), Integer::from(17)).err()));
+		let (curve, g, n) = toy_curve();
+		let config = RhoConfig::default();
+		let seed = Integer::new();
+		let q = curve.mul(&g, &Integer::from(7));
+		let off_curve = Point::Affine(Integer::from(5), Integer::from(2));
+		let unreduced = Point::Affine(Integer::from(22), Integer::from(1));
+		assert!(invalid(ecdlp_rho(&curve, &Integer::from(18), &config, &seed, &g, &q).err()));
+		assert!(invalid(ecdlp_rho(&curve, &Integer::from(17), &config, &seed, &g, &q).err()));
+		assert!(invalid(ecdlp_rho(&curve, &n, &config, &seed, &Point::Infinity, &q).err()));
+		assert!(invalid(ecdlp_rho(&curve, &n, &config, &seed, &off_curve, &q).err()));
+		assert!(invalid(ecdlp_rho(&curve, &n, &config, &seed, &unreduced, &q).err()));
+		assert!(invalid(ecdlp_rho(&curve, &n, &config, &seed, &g, &off_curve).err()));
+		// the coordinates are compared modulo p, g - g has no division by zero.
+		let sum = curve.add(&unreduced, &curve.neg(&g));
+		assert_eq!(sum, Point::Infinity);
+	}
+
+	#[test]
+	fn test_negation_map() {
+		// y^2 = x^3 + x + 5 over F_100003 has prime order 99707.
+		let curve =
+			Curve::new(Integer::from(1), Integer::from(5), Integer::from(100_003)).unwrap();
+		let g = Point::Affine(Integer::from(2), Integer::from(86_328));
+		let group = CurveGroup::new(curve.clone(), Integer::from(99_707));
+		assert!(curve.contains(&g));
+		for walk in [Walk::Pollard, Walk::Adding { r: 20 }] {
+			for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+				let mut steps = [0, 0];
+				for (negation_map, steps) in [false, true].into_iter().zip(&mut steps) {
+					let config = RhoConfig { cycle, walk, negation_map, ..Default::default() };
+					for k in 0..20 {
+						let key = Integer::from(k * 4_999 + 17);
+						let q = curve.mul(&g, &key);
+						let seed = Integer::from(k);
+						let (found, report) =
+							try_pollard_rho_report_in(&group, &config, 10, &seed, &g, &q).unwrap();
+						assert_eq!(found, key);
+						assert!(negation_map || report.fruitless_cycles == 0);
+						*steps += report.group_operations;
+					}
+				}
+				// the classes {P, -P} halve the search space, which saves about 1 - 1/sqrt(2) of the steps.
+				assert!(steps[1] < steps[0], "{:?} {:?}: {:?}", walk, cycle, steps);
+			}
+		}
+		// the multiplicative group has no cheap inverse.
+		let zp = MultiplicativeGroup::new(Integer::from(383), Integer::from(191));
+		let config = RhoConfig { negation_map: true, ..Default::default() };
+		let two = Integer::from(2);
+		let res = try_pollard_rho_report_in(&zp, &config, 10, &Integer::new(), &two, &two);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+}
@@ -0,0 +1,153 @@
+// ElGamal encryption over Z_p^* and the key recovery attack through the DLP solvers.
+// Source: Handbook of Applied Cryptography, algorithms 8.17 and 8.18.
+use crate::factor::{factorize_with, FactorConfig};
+use crate::generic::{RhoError, RhoResult};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::pohlig_hellman::{pohlig_hellman_with_factors, RHO_RESTARTS};
+use crate::utils::gen_bigint_range;
+use crate::validate::order_of;
+use crate::SolverConfig;
+
+/// Public key `h = g^x (mod p)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+	pub p: Integer,
+	pub g: Integer,
+	pub h: Integer,
+}
+
+/// Private exponent `x` together with its public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateKey {
+	pub public: PublicKey,
+	pub x: Integer,
+}
+
+/// Ciphertext `(c1, c2) = (g^k, m * h^k)` for an ephemeral exponent `k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+	pub c1: Integer,
+	pub c2: Integer,
+}
+
+impl PrivateKey {
+	/// Generates a key pair with a private exponent drawn from [1, p - 1).
+	/// # Arguments
+	/// * `p` - Prime modulus.
+	/// * `g` - Generator of the group.
+	/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+	pub fn generate(p: &Integer, g: &Integer, seed: &Integer) -> Self {
+		let mut rand = seeded_rand(seed);
+		let x = gen_bigint_range(&mut rand, &Integer::from(1), &Integer::from(p - 1));
+		let h = g.modpow(&x, p);
+		PrivateKey { public: PublicKey { p: p.clone(), g: g.clone(), h }, x }
+	}
+
+	/// Recovers the message `m = c2 * c1^(-x) (mod p)`, fails if `c1^x` is not invertible.
+	pub fn decrypt(&self, ciphertext: &Ciphertext) -> RhoResult<Integer> {
+		let p = &self.public.p;
+		let s = ciphertext.c1.modpow(&self.x, p);
+		let s_inv = s.modinv(p).ok_or_else(|| {
+			RhoError::InvalidParameters(format!("c1 = {} has no inverse modulo p", ciphertext.c1))
+		})?;
+		Ok((s_inv * &ciphertext.c2) % p)
+	}
+}
+
+impl PublicKey {
+	/// Encrypts the message `m` in [1, p) with an ephemeral exponent drawn from `seed`.
+	pub fn encrypt(&self, m: &Integer, seed: &Integer) -> Ciphertext {
+		let mut rand = seeded_rand(seed);
+		let k = gen_bigint_range(&mut rand, &Integer::from(1), &Integer::from(&self.p - 1));
+		let c1 = self.g.modpow(&k, &self.p);
+		let s = self.h.modpow(&k, &self.p);
+		Ciphertext { c1, c2: (s * m) % &self.p }
+	}
+}
+
+/// Recovers the private key of `public` by solving `h = g^x (mod p)` and decrypts `ciphertexts`.
+/// The order of `g` is computed from the factorization of `p - 1`, so the recovered `x`
+/// is reduced modulo that order, which decrypts exactly like the original exponent.
+/// # Arguments
+/// * `public` - Public key under attack.
+/// * `ciphertexts` - Ciphertexts encrypted under `public`.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+pub fn attack(
+	public: &PublicKey,
+	ciphertexts: &[Ciphertext],
+	seed: &Integer,
+) -> RhoResult<(PrivateKey, Vec<Integer>)> {
+	let config = SolverConfig { restarts: RHO_RESTARTS, ..Default::default() };
+	attack_with(&config, public, ciphertexts, seed)
+}
+
+/// Same as [`attack`] with the given solver settings of the prime-order subgroups,
+/// the budget of `config` also bounds the factorization of `p - 1`.
+pub fn attack_with(
+	config: &SolverConfig,
+	public: &PublicKey,
+	ciphertexts: &[Ciphertext],
+	seed: &Integer,
+) -> RhoResult<(PrivateKey, Vec<Integer>)> {
+	let PublicKey { p, g, h } = public;
+	let factor_config = FactorConfig { budget: config.budget.clone(), ..Default::default() };
+	let factors: Vec<(Integer, u32)> =
+		factorize_with(&Integer::from(p - 1), &factor_config)?.into_iter().collect();
+	let n = order_of(g, p, &factors)?;
+	// n divides p - 1, its factorization is the one of p - 1 with smaller exponents.
+	let n_factors: Vec<(Integer, u32)> = factors
+		.into_iter()
+		.map(|(q, e)| {
+			let e = (1..=e).take_while(|&k| n.is_divisible(&q.pow_u(k))).count() as u32;
+			(q, e)
+		})
+		.filter(|(_, e)| *e > 0)
+		.collect();
+	let x = pohlig_hellman_with_factors(config, &n_factors, seed, g, h, p, &n)?;
+	let private = PrivateKey { public: public.clone(), x };
+	let messages =
+		ciphertexts.iter().map(|c| private.decrypt(c)).collect::<RhoResult<Vec<_>>>()?;
+	Ok((private, messages))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::budget::{Budget, CancelToken, StopReason};
+
+	#[test]
+	fn test_elgamal_attack() {
+		// 3 generates Z_p^* for p = 2 * 3^3 * 5 * 17179869433 + 1, 9 only the squares.
+		let p = Integer::from(4638564746911u64);
+		for g in [3, 9] {
+			let private = PrivateKey::generate(&p, &Integer::from(g), &Integer::from(7));
+			let messages: Vec<Integer> =
+				[42u64, 1234567890, 4638564746910].iter().map(|m| Integer::from(*m)).collect();
+			let ciphertexts: Vec<Ciphertext> = messages
+				.iter()
+				.enumerate()
+				.map(|(i, m)| private.public.encrypt(m, &Integer::from(i)))
+				.collect();
+			for (m, c) in messages.iter().zip(&ciphertexts) {
+				assert_eq!(private.decrypt(c).as_ref(), Ok(m));
+			}
+			let (recovered, decrypted) =
+				attack(&private.public, &ciphertexts, &Integer::new()).expect("attack failed!");
+			assert_eq!(decrypted, messages);
+			let n = if g == 3 { Integer::from(&p - 1) } else { Integer::from(&p - 1) / 2u32 };
+			assert_eq!(recovered.x, Integer::from(&private.x % &n));
+		}
+		let private = PrivateKey::generate(&p, &Integer::from(3), &Integer::from(7));
+		// c1 = 0 is no output of encrypt and has no inverse.
+		let forged = Ciphertext { c1: Integer::new(), c2: Integer::from(5) };
+		assert!(matches!(private.decrypt(&forged), Err(RhoError::InvalidParameters(_))));
+		let res = attack(&private.public, &[forged], &Integer::new());
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+		let token = CancelToken::new();
+		token.cancel();
+		let config =
+			SolverConfig { budget: Budget::default().with_token(token), ..Default::default() };
+		let res = attack_with(&config, &private.public, &[], &Integer::new());
+		assert!(matches!(res, Err(RhoError::Cancelled(StopReason::Cancelled, _))));
+	}
+}
@@ -0,0 +1,389 @@
+// Integer factorization with Pollard's rho, Pollard's p-1 and Williams' p+1 methods.
+// Source: R. P. Brent, "An Improved Monte Carlo Factorization Algorithm", BIT, 1980.
+//         H. C. Williams, "A p+1 Method of Factoring", Mathematics of Computation, 1982.
+//         Handbook of Applied Cryptography, sections 3.2 and 4.2.
+use crate::budget::{Budget, StopReason};
+use crate::generic::{RhoResult, SolveReport};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::utils::gen_bigint_range;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+/// Primes below this bound are removed by trial division before any random splitting.
+const TRIAL_DIVISION_BOUND: u32 = 1 << 16;
+/// Number of Miller-Rabin rounds used to decide whether a cofactor is prime.
+const PRIME_REPS: u32 = 30;
+/// Number of products |x - y| accumulated before each gcd.
+const GCD_BATCH: u32 = 128;
+/// Starting values A of the Lucas sequences tried by the p+1 method, it only
+/// behaves differently from p-1 for a prime p when A^2 - 4 is a non-residue modulo p.
+const PP1_STARTS: [u32; 4] = [3, 5, 7, 11];
+
+/// Smoothness bounds of the p-1 and p+1 methods.
+/// Stage 1 finds a prime p when p-1 (resp. p+1) is a product of prime powers up to `b1`,
+/// stage 2 additionally allows a single prime factor in (b1, b2].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactorConfig {
+	pub b1: u64,
+	pub b2: u64,
+	/// Cancellation token and limits, the iterations are the rho steps of all splits.
+	pub budget: Budget,
+}
+
+impl Default for FactorConfig {
+	fn default() -> Self {
+		FactorConfig { b1: 2_000, b2: 50_000, budget: Budget::default() }
+	}
+}
+
+/// Sieve of Eratosthenes for all primes up to and including `bound`.
+pub(crate) fn primes_up_to(bound: u64) -> Vec<u64> {
+	let bound = bound as usize;
+	let mut sieve = vec![true; bound + 1];
+	let mut primes = Vec::new();
+	for i in 2..=bound {
+		if sieve[i] {
+			primes.push(i as u64);
+			for j in (i * i..=bound).step_by(i) {
+				sieve[j] = false;
+			}
+		}
+	}
+	primes
+}
+
+/// Largest power of the prime `q` not exceeding `bound`.
+fn prime_power(q: u64, bound: u64) -> u64 {
+	let mut q_e = q;
+	while q_e <= bound / q {
+		q_e *= q;
+	}
+	q_e
+}
+
+/// gcd(v, n) if it is a non-trivial factor of `n`.
+fn proper_gcd(v: &Integer, n: &Integer) -> Option<Integer> {
+	let g = v.gcd_with(n);
+	if g > 1 && g != *n {
+		Some(g)
+	} else {
+		None
+	}
+}
+
+/// Pollard's p-1 method, finds a prime factor p of `n` when p-1 is smooth for the bounds.
+/// Stage 1 computes a = 2^M (mod n) with M the product of the prime powers up to `b1`,
+/// stage 2 walks the primes q in (b1, b2] as a^q using the small gaps between primes.
+pub fn pollard_pm1(n: &Integer, config: &FactorConfig) -> Option<Integer> {
+	if *n < 4 {
+		return None
+	}
+	let primes = primes_up_to(config.b2.max(config.b1));
+	let reduce = |v: Integer| v.rem_euclid(n);
+	let mut a = Integer::from(2);
+	for &q in primes.iter().take_while(|&&q| q <= config.b1) {
+		a = a.modpow(&Integer::from(prime_power(q, config.b1)), n);
+	}
+	let a_1 = Integer::from(&a - 1);
+	if let Some(g) = proper_gcd(&a_1, n) {
+		return Some(g)
+	}
+	if a_1.gcd_with(n) == *n {
+		return None
+	}
+	let mut stage2 = primes.iter().skip_while(|&&q| q <= config.b1);
+	let mut prev = match stage2.next() {
+		Some(&q) => q,
+		None => return None,
+	};
+	let mut h = a.modpow(&Integer::from(prev), n);
+	let mut gaps: HashMap<u64, Integer> = HashMap::new();
+	let mut prod = reduce(Integer::from(&h - 1));
+	for (i, &q) in stage2.enumerate() {
+		let gap = gaps
+			.entry(q - prev)
+			.or_insert_with(|| a.modpow(&Integer::from(q - prev), n));
+		h = reduce(h * &*gap);
+		prod = reduce(prod * Integer::from(&h - 1));
+		prev = q;
+		if (i as u32).is_multiple_of(GCD_BATCH) {
+			if let Some(g) = proper_gcd(&prod, n) {
+				return Some(g)
+			}
+		}
+	}
+	proper_gcd(&prod, n)
+}
+
+/// Lucas sequence V_k(v) modulo `n` with V_0 = 2, V_1 = v and V_{i+1} = v*V_i - V_{i-1},
+/// evaluated by a ladder over the bits of `k`.
+fn lucas_v(v: &Integer, k: u64, n: &Integer) -> Integer {
+	if k == 0 {
+		return Integer::from(2)
+	}
+	let reduce = |v: Integer| v.rem_euclid(n);
+	// (x, y) = (V_j, V_{j+1}) for the leading bits j of k.
+	let mut x = v.clone();
+	let mut y = reduce(Integer::from(v * v) - 2u32);
+	for i in (0..63 - k.leading_zeros()).rev() {
+		if k >> i & 1 == 1 {
+			x = reduce(Integer::from(&x * &y) - v);
+			y = reduce(Integer::from(&y * &y) - 2u32);
+		} else {
+			y = reduce(Integer::from(&x * &y) - v);
+			x = reduce(Integer::from(&x * &x) - 2u32);
+		}
+	}
+	x
+}
+
+/// Williams' p+1 method, finds a prime factor p of `n` when p+1 is smooth for the bounds.
+/// Since V_j(V_k(A)) = V_{jk}(A), stage 1 raises V to every prime power up to `b1`,
+/// stage 2 tries V_q for each prime q in (b1, b2]. Several starting values are tried.
+pub fn williams_pp1(n: &Integer, config: &FactorConfig) -> Option<Integer> {
+	if *n < 4 {
+		return None
+	}
+	let primes = primes_up_to(config.b2.max(config.b1));
+	let reduce = |v: Integer| v.rem_euclid(n);
+	for start in PP1_STARTS {
+		let mut v = Integer::from(start);
+		for &q in primes.iter().take_while(|&&q| q <= config.b1) {
+			v = lucas_v(&v, prime_power(q, config.b1), n);
+		}
+		let v_2 = Integer::from(&v - 2);
+		if let Some(g) = proper_gcd(&v_2, n) {
+			return Some(g)
+		}
+		if v_2.gcd_with(n) == *n {
+			continue
+		}
+		let mut prod = Integer::from(1);
+		for (i, &q) in primes.iter().skip_while(|&&q| q <= config.b1).enumerate() {
+			prod = reduce(prod * (lucas_v(&v, q, n) - 2u32));
+			if (i as u32).is_multiple_of(GCD_BATCH) {
+				if let Some(g) = proper_gcd(&prod, n) {
+					return Some(g)
+				}
+			}
+		}
+		if let Some(g) = proper_gcd(&prod, n) {
+			return Some(g)
+		}
+	}
+	None
+}
+
+/// Finds a non-trivial factor of the composite `n` with Brent's variant of Pollard rho,
+/// iterating x -> x^2 + c (mod n) from a random start and constant drawn from `seed`.
+/// The differences are multiplied together and only every [`GCD_BATCH`] steps a gcd is taken.
+/// Returns `None` when this seed only finds the trivial factor `n`, another seed may succeed.
+pub fn pollard_rho_factor(n: &Integer, seed: &Integer) -> Option<Integer> {
+	rho_factor_within(n, seed, &Budget::default(), &mut 0).unwrap_or(None)
+}
+
+/// Same as [`pollard_rho_factor`], counting the steps in `iterations` until `budget` stops it.
+fn rho_factor_within(
+	n: &Integer,
+	seed: &Integer,
+	budget: &Budget,
+	iterations: &mut u64,
+) -> Result<Option<Integer>, StopReason> {
+	if *n < 4 {
+		return Ok(None)
+	}
+	if n.is_even() {
+		return Ok(Some(Integer::from(2)))
+	}
+	let mut rand = seeded_rand(seed);
+	let one = Integer::from(1);
+	let c = gen_bigint_range(&mut rand, &one, n);
+	let mut f = |x: &Integer| {
+		budget.check(*iterations)?;
+		*iterations += 1;
+		Ok((Integer::from(x * x) + &c).rem_euclid(n))
+	};
+	let mut y = gen_bigint_range(&mut rand, &one, n);
+	let mut x = y.clone();
+	let mut ys = y.clone();
+	let mut g = one.clone();
+	let mut q = one.clone();
+	let mut r = 1u64;
+	while g == 1 {
+		x = y.clone();
+		for _ in 0..r {
+			y = f(&y)?;
+		}
+		let mut k = 0;
+		while k < r && g == 1 {
+			ys = y.clone();
+			for _ in 0..(GCD_BATCH as u64).min(r - k) {
+				y = f(&y)?;
+				q = (q * Integer::from(&x - &y).abs_value()).rem_euclid(n);
+			}
+			g = q.gcd_with(n);
+			k += GCD_BATCH as u64;
+		}
+		r *= 2;
+	}
+	if g == *n {
+		// the batch overshot, redo its steps one gcd at a time.
+		loop {
+			ys = f(&ys)?;
+			g = Integer::from(&x - &ys).abs_value().gcd_with(n);
+			if g > 1 {
+				break
+			}
+		}
+	}
+	if g == *n {
+		Ok(None)
+	} else {
+		Ok(Some(g))
+	}
+}
+
+/// Splits the composite `n` into two non-trivial factors,
+/// trying the cheap p-1 and p+1 methods before rho.
+fn split(n: &Integer, config: &FactorConfig, iterations: &mut u64) -> Result<Integer, StopReason> {
+	config.budget.check(*iterations)?;
+	if let Some(d) = pollard_pm1(n, config).or_else(|| williams_pp1(n, config)) {
+		return Ok(d)
+	}
+	let mut seed = Integer::new();
+	loop {
+		if let Some(d) = rho_factor_within(n, &seed, &config.budget, iterations)? {
+			return Ok(d)
+		}
+		seed += 1;
+	}
+}
+
+/// Complete factorization of `|n|` as a map from each prime to its exponent.
+/// Small primes are removed by trial division, the cofactors are split recursively
+/// until Miller-Rabin declares every part prime. `0` and `1` have no prime factors.
+pub fn factorize(n: &Integer) -> BTreeMap<Integer, u32> {
+	factorize_with(n, &FactorConfig::default()).expect("Unlimited budget should not stop!")
+}
+
+/// Same as [`factorize`] with the given bounds for the p-1 and p+1 methods.
+/// Returns [`RhoError::Cancelled`](crate::generic::RhoError::Cancelled) with the rho steps
+/// taken so far once the budget of `config` is used up.
+pub fn factorize_with(n: &Integer, config: &FactorConfig) -> RhoResult<BTreeMap<Integer, u32>> {
+	let start = Instant::now();
+	let mut factors = BTreeMap::new();
+	let mut rem = n.abs_value();
+	if rem < 2 {
+		return Ok(factors)
+	}
+	let mut d = 2u32;
+	while d < TRIAL_DIVISION_BOUND && Integer::from(d) * d <= rem {
+		while rem.is_divisible_u(d) {
+			rem /= d;
+			*factors.entry(Integer::from(d)).or_insert(0) += 1;
+		}
+		d += if d == 2 { 1 } else { 2 };
+	}
+	let mut composites = vec![rem];
+	let mut iterations = 0u64;
+	while let Some(m) = composites.pop() {
+		if m == 1 {
+			continue
+		}
+		if m.is_probable_prime(PRIME_REPS) {
+			*factors.entry(m).or_insert(0) += 1;
+			continue
+		}
+		let d = split(&m, config, &mut iterations).map_err(|reason| {
+			let report = SolveReport {
+				iterations,
+				group_operations: iterations,
+				elapsed: start.elapsed(),
+				..Default::default()
+			};
+			reason.into_error(report)
+		})?;
+		composites.push(Integer::from(&m / &d));
+		composites.push(d);
+	}
+	Ok(factors)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::budget::CancelToken;
+	use crate::generic::RhoError;
+
+	#[test]
+	fn test_pollard_rho_factor() {
+		let n = Integer::from(1000000016000000063u64);
+		let d = (0..10)
+			.find_map(|seed| pollard_rho_factor(&n, &Integer::from(seed)))
+			.expect("Pollard rho should split n!");
+		assert!(d == 1000000007 || d == 1000000009);
+		assert_eq!(
+			pollard_rho_factor(&Integer::from(1000), &Integer::new()),
+			Some(Integer::from(2))
+		);
+	}
+
+	#[test]
+	fn test_factorize() {
+		let n = Integer::from(4638564746910u64);
+		let expected = [(2u64, 1u32), (3, 3), (5, 1), (17179869433, 1)];
+		let expected: BTreeMap<Integer, u32> =
+			expected.iter().map(|(q, e)| (Integer::from(*q), *e)).collect();
+		assert_eq!(factorize(&n), expected);
+		// (2^61 - 1) * (10^9 + 7)^2 needs the rho splitting for both primes.
+		let big = Integer::from(2305843009213693951u64);
+		let small = Integer::from(1000000007u64);
+		let n = Integer::from(&big * &small) * &small;
+		let expected: BTreeMap<Integer, u32> = [(small, 2), (big, 1)].into_iter().collect();
+		assert_eq!(factorize(&n), expected);
+		assert!(factorize(&Integer::from(1)).is_empty());
+	}
+
+	#[test]
+	fn test_pm1_pp1() {
+		// p - 1 is smooth apart from one stage-2 prime, p + 1 is smooth likewise,
+		// while both neighbours of the cofactor have a prime factor above 10^8.
+		let p_pm1 = Integer::from(1000000000091u64);
+		let p_pp1 = Integer::from(1000000000063u64);
+		let hard = Integer::from(10000000000037u64);
+		let config = FactorConfig::default();
+		let n = Integer::from(&p_pm1 * &hard);
+		assert_eq!(pollard_pm1(&n, &config), Some(p_pm1.clone()));
+		let n = Integer::from(&p_pp1 * &hard);
+		assert_eq!(williams_pp1(&n, &config), Some(p_pp1.clone()));
+		// without stage 2 neither is found.
+		let stage1 = FactorConfig { b1: 2_000, b2: 2_000, ..Default::default() };
+		assert_eq!(pollard_pm1(&Integer::from(&p_pm1 * &hard), &stage1), None);
+		assert_eq!(williams_pp1(&n, &stage1), None);
+		let n = Integer::from(&p_pm1 * &p_pp1) * &hard;
+		let expected: BTreeMap<Integer, u32> =
+			[(p_pp1, 1), (p_pm1, 1), (hard, 1)].into_iter().collect();
+		assert_eq!(factorize_with(&n, &config).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_factorize_budget() {
+		// without smoothness bounds only rho can split (2^61 - 1) * (10^9 + 7).
+		let n = Integer::from(2305843009213693951u64) * Integer::from(1000000007u64);
+		let budget = Budget::default().with_max_iterations(50);
+		let config = FactorConfig { b1: 1, b2: 1, budget };
+		match factorize_with(&n, &config) {
+			Err(RhoError::Cancelled(StopReason::IterationBudget, report)) => {
+				assert_eq!(report.iterations, 50);
+			},
+			res => panic!("unexpected result {:?}", res),
+		}
+		let token = CancelToken::new();
+		token.cancel();
+		let config = FactorConfig { budget: Budget::default().with_token(token), ..config };
+		let res = factorize_with(&n, &config);
+		assert!(matches!(res, Err(RhoError::Cancelled(StopReason::Cancelled, _))));
+		let config = FactorConfig { b1: 1, b2: 1, ..Default::default() };
+		assert_eq!(factorize_with(&n, &config).unwrap().len(), 2);
+	}
+}
@@ -1,5 +1,113 @@
-#[derive(Debug, Clone)]
-pub struct MappingError;
+use crate::budget::StopReason;
+use std::fmt;
+use std::time::Duration;
 
-// type alias for mapping result.
-pub type MapResult<T> = std::result::Result<T, MappingError>;
+/// Reasons the discrete log solvers can fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RhoError {
+	/// No usable collision was found within the iteration limit.
+	IterationLimit,
+	/// The walks collided with b1 == b2 (mod n), such a collision carries no information.
+	DegenerateCollision,
+	/// The collision equation has no solution satisfying base^x == y.
+	NonInvertibleCollision,
+	/// The input parameters do not describe a valid DLP instance.
+	InvalidParameters(String),
+	/// A snapshot of the walk could not be written or read.
+	Checkpoint(String),
+	/// The solver was stopped by its [`crate::budget::Budget`] before it finished,
+	/// with the statistics gathered until then.
+	Cancelled(StopReason, Box<SolveReport>),
+}
+
+impl fmt::Display for RhoError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RhoError::IterationLimit => write!(f, "Iteration limit reached without a collision"),
+			RhoError::DegenerateCollision => write!(f, "Degenerate collision with b1 == b2"),
+			RhoError::NonInvertibleCollision =>
+				write!(f, "Collision equation has no solution matching y"),
+			RhoError::InvalidParameters(reason) => write!(f, "Invalid parameters: {}", reason),
+			RhoError::Checkpoint(reason) => write!(f, "Checkpoint failed: {}", reason),
+			RhoError::Cancelled(reason, report) => match reason {
+				StopReason::Cancelled =>
+					write!(f, "Solver was cancelled after {} iterations", report.iterations),
+				StopReason::TimedOut =>
+					write!(f, "Solver timed out after {} iterations", report.iterations),
+				StopReason::IterationBudget =>
+					write!(f, "Solver used up its budget of {} iterations", report.iterations),
+			},
+		}
+	}
+}
+
+impl std::error::Error for RhoError {}
+
+// type alias for solver result.
+pub type RhoResult<T> = std::result::Result<T, RhoError>;
+
+/// Precondition of a DLP instance base^x = y (mod p) in the subgroup of order `n` which fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+	/// The modulus `p` is not prime.
+	ModulusNotPrime,
+	/// `base` is not in the range [1, p).
+	BaseOutOfRange,
+	/// `y` is not in the range [1, p).
+	TargetOutOfRange,
+	/// The order `n` does not divide `p - 1`.
+	OrderNotDividing,
+	/// `base^n` is not congruent to 1, or `base` does not have exactly the prime order `n`.
+	BaseOrderMismatch,
+	/// `y^n` is not congruent to 1, so `y` is not in the subgroup generated by `base`.
+	TargetNotInSubgroup,
+	/// The given factorization does not multiply to `p - 1` or has a non-prime factor.
+	FactorizationMismatch,
+}
+
+impl fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ValidationError::ModulusNotPrime => write!(f, "p is not prime"),
+			ValidationError::BaseOutOfRange => write!(f, "base is not in [1, p)"),
+			ValidationError::TargetOutOfRange => write!(f, "y is not in [1, p)"),
+			ValidationError::OrderNotDividing => write!(f, "n does not divide p - 1"),
+			ValidationError::BaseOrderMismatch => write!(f, "base does not have order n"),
+			ValidationError::TargetNotInSubgroup =>
+				write!(f, "y is not in the subgroup generated by base"),
+			ValidationError::FactorizationMismatch =>
+				write!(f, "factorization does not match p - 1"),
+		}
+	}
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for RhoError {
+	fn from(err: ValidationError) -> Self {
+		RhoError::InvalidParameters(err.to_string())
+	}
+}
+
+/// Statistics of a rho solver run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveReport {
+	/// Iterations of the cycle detection loop, summed over all attempts.
+	pub iterations: u64,
+	/// Steps of the iteration function, each costs one group operation,
+	/// summed over all attempts.
+	pub group_operations: u64,
+	/// Indices (i, j) of the walk points x_i == x_j the cycle detection stopped at.
+	pub collision: Option<(u64, u64)>,
+	/// Tail length λ, the index of the first point of the walk on its cycle.
+	/// Only measured on request, see [`crate::RhoConfig::measure_cycle`].
+	pub tail: Option<u64>,
+	/// Cycle length μ, known from Brent's cycle detection or measured on request.
+	pub cycle: Option<u64>,
+	/// Number of restarts with a new seed before the key was found.
+	pub restarts: usize,
+	/// Fruitless cycles of the negation map the walk escaped from, summed over all attempts.
+	pub fruitless_cycles: u64,
+	/// Wall-clock time of the run.
+	pub elapsed: Duration,
+}
@@ -0,0 +1,151 @@
+use crate::integer::{BigInteger, Integer};
+use std::fmt::Debug;
+
+/// A finite cyclic group the rho solvers can walk in.
+/// The walks only ever combine elements with [`Group::op`] and [`Group::pow`],
+/// collisions are detected through [`Group::equal`] and partitions are chosen
+/// from the canonical [`Group::encode`] of an element.
+pub trait Group {
+	/// Representation of the group elements.
+	type Element: Clone + Debug;
+
+	/// Neutral element of the group.
+	fn identity(&self) -> Self::Element;
+
+	/// Group operation `a * b`.
+	fn op(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+	/// Exponentiation `a^k` for a non-negative exponent `k`.
+	fn pow(&self, a: &Self::Element, k: &Integer) -> Self::Element;
+
+	/// Whether `a` and `b` represent the same group element.
+	fn equal(&self, a: &Self::Element, b: &Self::Element) -> bool;
+
+	/// Canonical encoding of an element, equal elements always have the same encoding.
+	fn encode(&self, a: &Self::Element) -> Integer;
+
+	/// Order `n` of the (sub)group the solvers work in, exponents are reduced modulo `n`.
+	fn order(&self) -> &Integer;
+
+	/// Inverse `a^-1`, only provided by groups where it is cheap enough for the
+	/// negation map of [`crate::RhoConfig::negation_map`], e.g. `-P` on elliptic curves.
+	fn inverse(&self, _a: &Self::Element) -> Option<Self::Element> {
+		None
+	}
+}
+
+/// The multiplicative group of integers modulo `p` restricted to a subgroup of order `n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiplicativeGroup {
+	p: Integer,
+	n: Integer,
+}
+
+impl MultiplicativeGroup {
+	/// # Arguments
+	/// * `p` - Modulus of the group.
+	/// * `n` - Order of the subgroup the DLP lives in.
+	pub fn new(p: Integer, n: Integer) -> Self {
+		MultiplicativeGroup { p, n }
+	}
+
+	/// Modulus of the group.
+	pub fn modulus(&self) -> &Integer {
+		&self.p
+	}
+}
+
+impl Group for MultiplicativeGroup {
+	type Element = Integer;
+
+	fn identity(&self) -> Integer {
+		Integer::from(1)
+	}
+
+	fn op(&self, a: &Integer, b: &Integer) -> Integer {
+		Integer::from(a * b).rem_euclid(&self.p)
+	}
+
+	fn pow(&self, a: &Integer, k: &Integer) -> Integer {
+		a.modpow(k, &self.p)
+	}
+
+	fn equal(&self, a: &Integer, b: &Integer) -> bool {
+		a == b
+	}
+
+	fn encode(&self, a: &Integer) -> Integer {
+		a.clone()
+	}
+
+	fn order(&self) -> &Integer {
+		&self.n
+	}
+}
+
+/// The subgroup of order `order` inside another group, e.g. a prime-order subgroup
+/// generated by `base^(n / q)`. All operations are delegated to the parent group.
+#[derive(Debug, Clone)]
+pub struct Subgroup<'a, G: Group> {
+	group: &'a G,
+	order: Integer,
+}
+
+impl<'a, G: Group> Subgroup<'a, G> {
+	pub fn new(group: &'a G, order: Integer) -> Self {
+		Subgroup { group, order }
+	}
+}
+
+impl<'a, G: Group> Group for Subgroup<'a, G> {
+	type Element = G::Element;
+
+	fn identity(&self) -> G::Element {
+		self.group.identity()
+	}
+
+	fn op(&self, a: &G::Element, b: &G::Element) -> G::Element {
+		self.group.op(a, b)
+	}
+
+	fn pow(&self, a: &G::Element, k: &Integer) -> G::Element {
+		self.group.pow(a, k)
+	}
+
+	fn equal(&self, a: &G::Element, b: &G::Element) -> bool {
+		self.group.equal(a, b)
+	}
+
+	fn encode(&self, a: &G::Element) -> Integer {
+		self.group.encode(a)
+	}
+
+	fn order(&self) -> &Integer {
+		&self.order
+	}
+
+	fn inverse(&self, a: &G::Element) -> Option<G::Element> {
+		self.group.inverse(a)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{pollard_rho_in, RhoConfig};
+
+	#[test]
+	fn test_multiplicative_group() {
+		let group = MultiplicativeGroup::new(Integer::from(383), Integer::from(191));
+		let two = Integer::from(2);
+		assert_eq!(group.pow(&two, group.order()), group.identity());
+		assert_eq!(group.op(&two, &group.identity()), two);
+		let y = group.pow(&two, &Integer::from(57));
+		let key = (0..10)
+			.find_map(|i| {
+				pollard_rho_in(&group, &RhoConfig::default(), &Integer::from(i), &two, &y).ok()
+			})
+			.expect("Pollard rho should find the key!");
+		assert_eq!(key, 57);
+	}
+}
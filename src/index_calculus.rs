@@ -0,0 +1,333 @@
+// Index calculus for the DLP in a prime-order subgroup of Z_p^*.
+// Source: Handbook of Applied Cryptography, algorithm 3.68.
+//         B. A. LaMacchia and A. M. Odlyzko, "Solving Large Sparse Linear Systems over
+//         Finite Fields", CRYPTO 1990 (structured Gaussian elimination).
+use crate::factor::primes_up_to;
+use crate::budget::Budget;
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::utils::gen_bigint_range;
+use crate::validate::validate;
+use std::time::Instant;
+
+/// Configuration of the index calculus solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexCalculusConfig {
+	/// Largest prime of the factor base, `None` picks exp(sqrt(ln p * ln ln p) / 2).
+	pub factor_base_bound: Option<u32>,
+	/// Number of relations collected beyond the size of the factor base.
+	pub extra_relations: usize,
+	/// Maximum number of candidates tested for smoothness, in each of the
+	/// relation collection and the descent.
+	pub max_trials: u64,
+	/// Cancellation token and limits, every smoothness test is an iteration.
+	pub budget: Budget,
+}
+
+impl Default for IndexCalculusConfig {
+	fn default() -> Self {
+		IndexCalculusConfig {
+			factor_base_bound: None,
+			extra_relations: 20,
+			max_trials: 1 << 24,
+			budget: Budget::default(),
+		}
+	}
+}
+
+impl IndexCalculusConfig {
+	fn bound(&self, p: &Integer) -> u32 {
+		self.factor_base_bound.unwrap_or_else(|| {
+			let ln_p = p.significant_bits() as f64 * std::f64::consts::LN_2;
+			let bound = ((ln_p * ln_p.ln()).sqrt() / 2.0).exp();
+			bound.clamp(64.0, (1 << 20) as f64) as u32
+		})
+	}
+}
+
+/// Relation base^(k*h) = prod (l_i^h)^(e_i) (mod p) between the factor base primes l_i.
+struct Relation {
+	exponents: Vec<(usize, u32)>,
+	rhs: Integer,
+}
+
+/// Exponents of `v` over the factor base, `None` unless `v` is smooth.
+fn smooth_exponents(v: &Integer, primes: &[u32]) -> Option<Vec<(usize, u32)>> {
+	let mut rem = v.clone();
+	let mut exponents = Vec::new();
+	for (i, &q) in primes.iter().enumerate() {
+		if rem == 1 {
+			break
+		}
+		let mut e = 0;
+		while rem.is_divisible_u(q) {
+			rem /= q;
+			e += 1;
+		}
+		if e > 0 {
+			exponents.push((i, e));
+		}
+	}
+	if rem == 1 {
+		Some(exponents)
+	} else {
+		None
+	}
+}
+
+/// Computes `x` = a mod n for the DLP base**x mod p == y with index calculus.
+/// Each factor base prime `l` is mapped into the subgroup as `l^h` with `h = (p - 1)/n`,
+/// so `n` has to be prime and must not divide `h`. Logs of `l^h` are found from smooth
+/// powers of `base`, then a smooth `y * base^s` gives the log of `y`.
+/// # Arguments
+/// * `config` - Factor base and search limits.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`, must be prime.
+pub fn index_calculus(
+	config: &IndexCalculusConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	index_calculus_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`index_calculus`] but also returns the statistics, every smoothness test
+/// is an iteration.
+pub fn index_calculus_report(
+	config: &IndexCalculusConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	if !n.is_probable_prime(30) {
+		return Err(RhoError::InvalidParameters(format!("subgroup order {} is not prime", n)))
+	}
+	let h = Integer::from(p - 1) / n;
+	let h_inv =
+		h.modinv(n).ok_or_else(|| {
+			RhoError::InvalidParameters(format!("n^2 divides p - 1 for n = {}", n))
+		})?;
+	let primes: Vec<u32> =
+		primes_up_to(config.bound(p) as u64).into_iter().map(|q| q as u32).collect();
+	let mut rand = seeded_rand(seed);
+	let one = Integer::from(1);
+	let start = Instant::now();
+	let mut iterations = 0;
+	let report = |iterations: u64| SolveReport {
+		iterations,
+		elapsed: start.elapsed(),
+		..Default::default()
+	};
+	let check = |iterations: &mut u64| {
+		config.budget.check(*iterations).map_err(|reason| reason.into_error(report(*iterations)))?;
+		*iterations += 1;
+		Ok::<(), RhoError>(())
+	};
+	let mut relations = Vec::new();
+	let mut trials = 0;
+	while relations.len() < primes.len() + config.extra_relations {
+		if trials == config.max_trials {
+			return Err(RhoError::IterationLimit)
+		}
+		trials += 1;
+		check(&mut iterations)?;
+		let k = gen_bigint_range(&mut rand, &one, n);
+		let v = base.modpow(&k, p);
+		if let Some(exponents) = smooth_exponents(&v, &primes) {
+			relations.push(Relation { exponents, rhs: (k * &h).rem_euclid(n) });
+		}
+	}
+	let logs = solve_relations(&relations, primes.len(), n);
+	// a log is only kept if base^L == l^h, which also rejects the underdetermined ones.
+	let logs: Vec<Option<Integer>> = logs
+		.into_iter()
+		.zip(&primes)
+		.map(|(log, &q)| {
+			log.filter(|log| {
+				let l_h = Integer::from(q).modpow(&h, p);
+				base.modpow(log, p) == l_h
+			})
+		})
+		.collect();
+	// individual log: y * base^s = prod l_i^(f_i)  ==>  x = h^(-1) * sum f_i * L_i - s
+	for _ in 0..config.max_trials {
+		check(&mut iterations)?;
+		let s = gen_bigint_range(&mut rand, &Integer::new(), n);
+		let v = (y * base.modpow(&s, p)) % p;
+		let Some(exponents) = smooth_exponents(&v, &primes) else { continue };
+		let mut sum = Integer::new();
+		let known = exponents.iter().all(|(i, f)| match &logs[*i] {
+			Some(log) => {
+				sum += Integer::from(log * *f);
+				true
+			},
+			None => false,
+		});
+		if !known {
+			continue
+		}
+		let x = (sum * &h_inv - s).rem_euclid(n);
+		if base.modpow(&x, p) == *y {
+			return Ok((x, report(iterations)))
+		}
+	}
+	Err(RhoError::IterationLimit)
+}
+
+/// Solves the relations for the logs of the factor base modulo the prime `n`.
+/// Structured Gaussian elimination first drops columns no relation touches and
+/// peels off columns that only occur in a single relation, the remaining core is
+/// reduced by dense Gaussian elimination and the peeled columns are back-substituted.
+fn solve_relations(relations: &[Relation], columns: usize, n: &Integer) -> Vec<Option<Integer>> {
+	let mut row_alive = vec![true; relations.len()];
+	let mut col_alive = vec![true; columns];
+	let mut singletons = Vec::new();
+	loop {
+		let mut weight = vec![0usize; columns];
+		let mut last_row = vec![0usize; columns];
+		for (r, relation) in relations.iter().enumerate().filter(|(r, _)| row_alive[*r]) {
+			for &(c, _) in &relation.exponents {
+				weight[c] += 1;
+				last_row[c] = r;
+			}
+		}
+		let mut changed = false;
+		for c in 0..columns {
+			if !col_alive[c] {
+				continue
+			}
+			if weight[c] == 0 {
+				col_alive[c] = false;
+			} else if weight[c] == 1 && row_alive[last_row[c]] {
+				row_alive[last_row[c]] = false;
+				col_alive[c] = false;
+				singletons.push((last_row[c], c));
+				changed = true;
+			}
+		}
+		if !changed {
+			break
+		}
+	}
+	let mut logs: Vec<Option<Integer>> = vec![None; columns];
+	let dense_cols: Vec<usize> = (0..columns).filter(|c| col_alive[*c]).collect();
+	let width = dense_cols.len();
+	let mut index = vec![usize::MAX; columns];
+	for (j, &c) in dense_cols.iter().enumerate() {
+		index[c] = j;
+	}
+	let mut matrix: Vec<Vec<Integer>> = relations
+		.iter()
+		.enumerate()
+		.filter(|(r, _)| row_alive[*r])
+		.map(|(_, relation)| {
+			let mut row = vec![Integer::new(); width + 1];
+			for &(c, e) in &relation.exponents {
+				row[index[c]] = Integer::from(e);
+			}
+			row[width] = relation.rhs.clone();
+			row
+		})
+		.collect();
+	let mut pivots = Vec::new();
+	for j in 0..width {
+		let rank = pivots.len();
+		let Some(r) = (rank..matrix.len()).find(|r| !matrix[*r][j].is_divisible(n)) else {
+			continue
+		};
+		matrix.swap(rank, r);
+		let inv = matrix[rank][j].modinv(n).unwrap();
+		for v in matrix[rank].iter_mut() {
+			*v = Integer::from(&*v * &inv).rem_euclid(n);
+		}
+		let pivot_row = matrix[rank].clone();
+		for (r, row) in matrix.iter_mut().enumerate() {
+			if r == rank || row[j] == 0 {
+				continue
+			}
+			let factor = row[j].clone();
+			for (v, pv) in row.iter_mut().zip(&pivot_row) {
+				*v -= Integer::from(&factor * pv);
+				*v = v.rem_euclid(n);
+			}
+		}
+		pivots.push(j);
+	}
+	for (rank, &j) in pivots.iter().enumerate() {
+		let row = &matrix[rank];
+		// the log is determined only if no free column is left in its row.
+		if (0..width).all(|k| k == j || row[k] == 0) {
+			logs[dense_cols[j]] = Some(row[width].clone());
+		}
+	}
+	for &(r, c) in singletons.iter().rev() {
+		let mut rhs = relations[r].rhs.clone();
+		let mut coefficient = 0;
+		let known = relations[r].exponents.iter().all(|&(k, e)| {
+			if k == c {
+				coefficient = e;
+				return true
+			}
+			match &logs[k] {
+				Some(log) => {
+					rhs -= Integer::from(log * e);
+					true
+				},
+				None => false,
+			}
+		});
+		if !known {
+			continue
+		}
+		if let Some(inv) = Integer::from(coefficient).modinv(n) {
+			logs[c] = Some((rhs * inv).rem_euclid(n));
+		}
+	}
+	logs
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_index_calculus() {
+		// 10^9 + 7 = 2 * 500000003 + 1 is a safe prime, 4 generates the subgroup of squares.
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let base = Integer::from(4);
+		let config = IndexCalculusConfig { factor_base_bound: Some(500), ..Default::default() };
+		for key in [0u64, 1, 123_456_789, 500_000_002] {
+			let key = Integer::from(key);
+			let y = base.modpow(&key, &p);
+			assert_eq!(index_calculus(&config, &Integer::new(), &base, &y, &p, &n), Ok(key));
+		}
+		// a subgroup of order 17179869433 with cofactor h = 270.
+		let p = Integer::from(4638564746911u64);
+		let n = Integer::from(17179869433u64);
+		let base = Integer::from(3).modpow(&Integer::from(270), &p);
+		let key = Integer::from(9876543210u64);
+		let y = base.modpow(&key, &p);
+		let res =
+			index_calculus(&IndexCalculusConfig::default(), &Integer::new(), &base, &y, &p, &n);
+		assert_eq!(res, Ok(key));
+		let res = index_calculus(
+			&config,
+			&Integer::new(),
+			&Integer::from(3),
+			&y,
+			&p,
+			&(p.clone() - 1u32),
+		);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+}
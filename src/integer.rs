@@ -0,0 +1,123 @@
+// Big integer backends of the crate.
+// `rug` wraps GMP and is the default. `num-bigint` is pure Rust, for targets where GMP
+// cannot be built, e.g. WASM or Windows without a C toolchain:
+//     cargo build --no-default-features --features num-bigint
+// The backends use different random number generators, so a seed leads to different walks,
+// and thus to different iteration counts, on each of them.
+#[cfg(feature = "rug")]
+mod gmp;
+#[cfg(all(feature = "num-bigint", not(feature = "rug")))]
+mod bigint;
+
+#[cfg(feature = "rug")]
+pub use gmp::{seeded_rand, Integer, RandState};
+#[cfg(all(feature = "num-bigint", not(feature = "rug")))]
+pub use bigint::{seeded_rand, Integer, RandState};
+
+/// Name of the backend the crate is built with.
+#[cfg(feature = "rug")]
+pub const BACKEND: &str = "rug";
+#[cfg(all(feature = "num-bigint", not(feature = "rug")))]
+pub const BACKEND: &str = "num-bigint";
+
+#[cfg(not(any(feature = "rug", feature = "num-bigint")))]
+compile_error!("one of the features `rug` or `num-bigint` must be enabled");
+
+/// The integer operations whose implementation differs between the backends.
+/// Both backends also have the methods `mod_u`, `is_divisible`, `is_divisible_u`, `is_even`,
+/// `significant_bits`, `get_bit`, `to_u64` and `to_u64_wrapping` of `rug::Integer`.
+pub trait BigInteger: Sized {
+	/// `self**exp mod m` in [0, m) for a non-negative `exp`.
+	fn modpow(&self, exp: &Self, m: &Self) -> Self;
+	/// Inverse of `self` modulo `m` in [0, m), `None` if they are not coprime.
+	fn modinv(&self, m: &Self) -> Option<Self>;
+	/// Non-negative greatest common divisor.
+	fn gcd_with(&self, other: &Self) -> Self;
+	/// Euclidean quotient and remainder, the remainder is in [0, |d|).
+	fn div_rem_euclid(&self, d: &Self) -> (Self, Self);
+	/// Euclidean remainder in [0, |m|).
+	fn rem_euclid(&self, m: &Self) -> Self {
+		self.div_rem_euclid(m).1
+	}
+	/// Uniformly random integer in [0, self) for a positive `self`.
+	fn sample_below(&self, rand: &mut RandState) -> Self;
+	/// Integer square root of a non-negative integer.
+	fn isqrt(&self) -> Self;
+	fn abs_value(&self) -> Self;
+	fn pow_u(&self, e: u32) -> Self;
+	/// `false` only if `self` is certainly composite, `reps` is the number of Miller-Rabin rounds.
+	fn is_probable_prime(&self, reps: u32) -> bool;
+	/// Parses an optionally signed integer in the given radix.
+	fn read_radix(s: &str, radix: u32) -> Option<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_big_integer() {
+		let p = Integer::from(383);
+		let two = Integer::from(2);
+		assert_eq!(two.modpow(&Integer::from(191), &p), 1);
+		assert_eq!(Integer::from(-3).modpow(&Integer::from(3), &p), 356);
+		assert_eq!(Integer::from(-7).modinv(&p), Some(Integer::from(164)));
+		assert_eq!(Integer::from(6).modinv(&Integer::from(9)), None);
+		assert_eq!(Integer::from(-12).gcd_with(&Integer::from(18)), 6);
+		let (q, r) = Integer::from(-7).div_rem_euclid(&Integer::from(3));
+		assert_eq!((q, r), (Integer::from(-3), Integer::from(2)));
+		assert_eq!(Integer::from(-7).rem_euclid(&Integer::from(-3)), 2);
+		assert_eq!(Integer::from(99).isqrt(), 9);
+		assert_eq!(Integer::from(-5).abs_value(), 5);
+		assert_eq!(Integer::from(3).pow_u(40), Integer::from(3u64.pow(40)));
+		assert!(p.is_probable_prime(30));
+		assert!(!Integer::from(561).is_probable_prime(30));
+		assert!(Integer::from(1_000_000_007u64).is_probable_prime(30));
+		assert!(!Integer::from(1).is_probable_prime(30));
+		// strong pseudoprimes to the first 11 and the first 13 prime bases.
+		assert!(!Integer::from(3_825_123_056_546_413_051u64).is_probable_prime(11));
+		let n = Integer::from(3_317_044_064_679_887_385_961_981u128);
+		assert!(!n.is_probable_prime(13));
+		assert!(!n.is_probable_prime(30));
+		assert_eq!(Integer::read_radix("-1f", 16), Some(Integer::from(-31)));
+		assert_eq!(Integer::read_radix("1g", 16), None);
+		assert_eq!(Integer::from(-7).mod_u(3), 2);
+		assert!(Integer::from(-12).is_divisible(&Integer::from(4)));
+		assert!(!Integer::from(13).is_divisible_u(4));
+		assert!(Integer::from(-2).is_even());
+		assert_eq!(Integer::from(-255).significant_bits(), 8);
+		assert!(Integer::from(-2).get_bit(100));
+		assert!(!Integer::from(5).get_bit(1));
+		assert_eq!(Integer::from(-1).to_u64(), None);
+		assert_eq!((Integer::from(1) << 70u32).to_u64_wrapping(), 0);
+		assert_eq!(Integer::from(-1).to_u64_wrapping(), u64::MAX);
+		assert_eq!((Integer::from(u64::MAX) + 5u32).to_u64_wrapping(), 4);
+	}
+
+	#[test]
+	fn test_is_probable_prime() {
+		let is_prime = |n: u64| n > 1 && (2..).take_while(|q| q * q <= n).all(|q| !n.is_multiple_of(q));
+		for n in 50_000..53_000u64 {
+			assert_eq!(Integer::from(n).is_probable_prime(2), is_prime(n), "{}", n);
+		}
+		// 829 * 1657 passes the Miller-Rabin rounds to bases 2 and 3.
+		assert!(!Integer::from(1_373_653).is_probable_prime(0));
+		let p = (Integer::from(1) << 127u32) - 1u32;
+		assert!(p.is_probable_prime(30));
+		assert!(!Integer::from(&p * &p).is_probable_prime(30));
+	}
+
+	#[test]
+	fn test_sample_below() {
+		let bound = Integer::from(1) << 70u32;
+		let mut rand = seeded_rand(&Integer::from(7));
+		let draws: Vec<Integer> = (0..50).map(|_| bound.sample_below(&mut rand)).collect();
+		assert!(draws.iter().all(|x| *x >= 0 && *x < bound));
+		assert!(draws.iter().any(|x| x.significant_bits() > 64));
+		// the generator is deterministic for a seed.
+		let mut rand = seeded_rand(&Integer::from(7));
+		assert!(draws.iter().all(|x| bound.sample_below(&mut rand) == *x));
+		let mut rand = seeded_rand(&Integer::from(1));
+		assert!((0..20).all(|_| Integer::from(3).sample_below(&mut rand) < 3));
+	}
+}
@@ -0,0 +1,544 @@
+// Pure Rust backend on `num-bigint`.
+// Source: D. Blackman and S. Vigna, "Scrambled Linear Pseudorandom Number Generators",
+//         ACM Transactions on Mathematical Software 47 (2021), for xoshiro256**.
+use super::BigInteger;
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer as _;
+use num_traits::{Euclid, Num, One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{
+	Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+	ShrAssign, Sub, SubAssign,
+};
+use std::str::FromStr;
+
+/// Primes tried as divisors before the probabilistic tests.
+const SMALL_PRIMES: [u32; 50] = [
+	2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+	97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181,
+	191, 193, 197, 199, 211, 223, 227, 229,
+];
+
+/// Arbitrary precision integer with the part of the interface of `rug::Integer` the crate
+/// uses. Arithmetic on references returns a new integer right away.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(BigInt);
+
+impl Integer {
+	pub const ZERO: Integer = Integer(BigInt::ZERO);
+
+	pub fn new() -> Self {
+		Integer::ZERO
+	}
+
+	/// Euclidean remainder modulo a non-zero `m`.
+	pub fn mod_u(&self, m: u32) -> u32 {
+		self.0.mod_floor(&BigInt::from(m)).to_u32().unwrap()
+	}
+
+	pub fn is_divisible(&self, d: &Self) -> bool {
+		if d.0.is_zero() {
+			return self.0.is_zero()
+		}
+		self.0.is_multiple_of(&d.0)
+	}
+
+	pub fn is_divisible_u(&self, d: u32) -> bool {
+		self.is_divisible(&Integer::from(d))
+	}
+
+	pub fn is_even(&self) -> bool {
+		self.0.is_even()
+	}
+
+	/// Number of bits of the absolute value.
+	pub fn significant_bits(&self) -> u32 {
+		self.0.bits() as u32
+	}
+
+	/// Bit `i` of the two's complement representation.
+	pub fn get_bit(&self, i: u32) -> bool {
+		self.0.bit(i as u64)
+	}
+
+	pub fn to_u64(&self) -> Option<u64> {
+		self.0.to_u64()
+	}
+
+	/// The lowest 64 bits of the two's complement representation.
+	pub fn to_u64_wrapping(&self) -> u64 {
+		let low = self.0.magnitude().iter_u64_digits().next().unwrap_or(0);
+		if self.0.is_negative() {
+			low.wrapping_neg()
+		} else {
+			low
+		}
+	}
+}
+
+/// xoshiro256** generator.
+#[derive(Debug, Clone)]
+pub struct RandState {
+	s: [u64; 4],
+}
+
+impl RandState {
+	fn next_u64(&mut self) -> u64 {
+		let res = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+		let t = self.s[1] << 17;
+		self.s[2] ^= self.s[0];
+		self.s[3] ^= self.s[1];
+		self.s[1] ^= self.s[2];
+		self.s[0] ^= self.s[3];
+		self.s[2] ^= t;
+		self.s[3] = self.s[3].rotate_left(45);
+		res
+	}
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+	*state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+	let mut z = *state;
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
+
+/// xoshiro256** whose state is expanded from all limbs of `seed` by splitmix64.
+pub fn seeded_rand(seed: &Integer) -> RandState {
+	let mut mix = (seed.0.sign() == Sign::Minus) as u64;
+	for limb in seed.0.magnitude().iter_u64_digits() {
+		mix = splitmix64(&mut mix) ^ limb;
+	}
+	let mut s = [0u64; 4];
+	for word in s.iter_mut() {
+		*word = splitmix64(&mut mix);
+	}
+	RandState { s }
+}
+
+impl BigInteger for Integer {
+	fn modpow(&self, exp: &Self, m: &Self) -> Self {
+		assert!(!exp.0.is_negative(), "Exponent should be non-negative!");
+		let res = Integer(self.0.modpow(&exp.0, &m.0));
+		// num-bigint takes the sign of the modulus, rug the one of [0, |m|).
+		if res.0.is_negative() {
+			res + m.abs_value()
+		} else {
+			res
+		}
+	}
+
+	fn modinv(&self, m: &Self) -> Option<Self> {
+		let m = m.0.abs();
+		if m.is_one() {
+			return Some(Integer::ZERO)
+		}
+		self.0.mod_floor(&m).modinv(&m).map(Integer)
+	}
+
+	fn gcd_with(&self, other: &Self) -> Self {
+		Integer(self.0.gcd(&other.0))
+	}
+
+	fn div_rem_euclid(&self, d: &Self) -> (Self, Self) {
+		(Integer(self.0.div_euclid(&d.0)), Integer(self.0.rem_euclid(&d.0)))
+	}
+
+	fn sample_below(&self, rand: &mut RandState) -> Self {
+		assert!(self.0.is_positive(), "Bound should be positive!");
+		let bits = self.0.bits();
+		let words = bits.div_ceil(64) as usize;
+		let top = bits % 64;
+		// rejection sampling, every draw succeeds with probability above 1/2.
+		loop {
+			let mut limbs: Vec<u64> = (0..words).map(|_| rand.next_u64()).collect();
+			if top != 0 {
+				limbs[words - 1] &= (1 << top) - 1;
+			}
+			let x = BigInt::from_biguint(Sign::Plus, BigUint::from_slice(&to_u32_digits(&limbs)));
+			if x < self.0 {
+				return Integer(x)
+			}
+		}
+	}
+
+	fn isqrt(&self) -> Self {
+		Integer(self.0.sqrt())
+	}
+
+	fn abs_value(&self) -> Self {
+		Integer(self.0.abs())
+	}
+
+	fn pow_u(&self, e: u32) -> Self {
+		Integer(self.0.pow(e))
+	}
+
+	/// Baillie-PSW, i.e. a Miller-Rabin round to base 2 and a strong Lucas test, which has no
+	/// known counterexample, followed by `reps` Miller-Rabin rounds to random bases.
+	/// Fixed bases would not do, composites which pass all rounds to the first prime bases
+	/// can be constructed on purpose.
+	fn is_probable_prime(&self, reps: u32) -> bool {
+		let n = &self.0;
+		if *n < BigInt::from(2) {
+			return false
+		}
+		for q in SMALL_PRIMES {
+			if *n == BigInt::from(q) {
+				return true
+			}
+			if n.is_multiple_of(&BigInt::from(q)) {
+				return false
+			}
+		}
+		if !miller_rabin(n, &BigInt::from(2)) || !strong_lucas(n) {
+			return false
+		}
+		// the bases are random but the answer is the same on every call.
+		let mut rand = seeded_rand(self);
+		let range = Integer(n - 3u32);
+		(0..reps).all(|_| miller_rabin(n, &(range.sample_below(&mut rand).0 + 2u32)))
+	}
+
+	fn read_radix(s: &str, radix: u32) -> Option<Self> {
+		BigInt::from_str_radix(s, radix).ok().map(Integer)
+	}
+}
+
+/// Whether the odd `n > 2` is a strong probable prime to the base `a`.
+fn miller_rabin(n: &BigInt, a: &BigInt) -> bool {
+	let n_1 = n - 1u32;
+	let s = n_1.trailing_zeros().unwrap();
+	let mut x = a.modpow(&(&n_1 >> s), n);
+	if x.is_one() || x == n_1 {
+		return true
+	}
+	for _ in 1..s {
+		x = &x * &x % n;
+		if x == n_1 {
+			return true
+		}
+	}
+	false
+}
+
+/// Whether the odd `n` without small factors is a strong Lucas probable prime for the
+/// parameters `P = 1`, `Q = (1 - D)/4` of Selfridge's method A.
+fn strong_lucas(n: &BigInt) -> bool {
+	let root = n.sqrt();
+	if &root * &root == *n {
+		return false
+	}
+	// first D of 5, -7, 9, -11, ... with the Jacobi symbol (D/n) = -1.
+	let mut d = 5i64;
+	while jacobi(&BigInt::from(d), n) != -1 {
+		d = if d > 0 { -d - 2 } else { -d + 2 };
+	}
+	let q = BigInt::from((1 - d) / 4);
+	let d = BigInt::from(d);
+	let half = |x: BigInt| {
+		let x = x.mod_floor(n);
+		if x.is_odd() {
+			(x + n) >> 1u32
+		} else {
+			x >> 1u32
+		}
+	};
+	// U_k, V_k and Q^k for the prefixes k of the bits of n + 1 = d * 2^s.
+	let n_1 = n + 1u32;
+	let s = n_1.trailing_zeros().unwrap();
+	let k = &n_1 >> s;
+	let (mut u, mut v, mut q_k) = (BigInt::one(), BigInt::one(), q.mod_floor(n));
+	for i in (0..k.bits() - 1).rev() {
+		// U_2k = U_k * V_k, V_2k = V_k^2 - 2Q^k
+		u = &u * &v % n;
+		v = (&v * &v - &q_k * 2u32).mod_floor(n);
+		q_k = &q_k * &q_k % n;
+		if k.bit(i) {
+			// U_k+1 = (U_k + V_k)/2, V_k+1 = (D*U_k + V_k)/2
+			(u, v) = (half(&u + &v), half(&d * &u + &v));
+			q_k = (&q_k * &q).mod_floor(n);
+		}
+	}
+	if u.is_zero() || v.is_zero() {
+		return true
+	}
+	for _ in 1..s {
+		v = (&v * &v - &q_k * 2u32).mod_floor(n);
+		if v.is_zero() {
+			return true
+		}
+		q_k = &q_k * &q_k % n;
+	}
+	false
+}
+
+/// Jacobi symbol (a/n) for an odd positive `n`.
+fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+	let (mut a, mut n) = (a.mod_floor(n), n.clone());
+	let mut res = 1;
+	while !a.is_zero() {
+		let twos = a.trailing_zeros().unwrap();
+		a >>= twos;
+		let n_8 = (&n % 8u32).to_u32().unwrap();
+		if twos % 2 == 1 && (n_8 == 3 || n_8 == 5) {
+			res = -res;
+		}
+		if (&a % 4u32).is_one() || (&n % 4u32).is_one() {
+			(a, n) = (n % &a, a);
+		} else {
+			(a, n) = (n % &a, a);
+			res = -res;
+		}
+	}
+	if n.is_one() {
+		res
+	} else {
+		0
+	}
+}
+
+fn to_u32_digits(limbs: &[u64]) -> Vec<u32> {
+	limbs.iter().flat_map(|&limb| [limb as u32, (limb >> 32) as u32]).collect()
+}
+
+impl fmt::Display for Integer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.0, f)
+	}
+}
+
+impl fmt::Debug for Integer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.0, f)
+	}
+}
+
+/// Error of parsing an [`Integer`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntegerError;
+
+impl fmt::Display for ParseIntegerError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "invalid integer")
+	}
+}
+
+impl std::error::Error for ParseIntegerError {}
+
+impl FromStr for Integer {
+	type Err = ParseIntegerError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Integer::read_radix(s, 10).ok_or(ParseIntegerError)
+	}
+}
+
+impl From<&Integer> for Integer {
+	fn from(x: &Integer) -> Self {
+		x.clone()
+	}
+}
+
+macro_rules! primitives {
+	($($t:ty),*) => {$(
+		impl From<$t> for Integer {
+			fn from(x: $t) -> Self {
+				Integer(BigInt::from(x))
+			}
+		}
+
+		impl PartialEq<$t> for Integer {
+			fn eq(&self, other: &$t) -> bool {
+				self.0 == BigInt::from(*other)
+			}
+		}
+
+		impl PartialEq<Integer> for $t {
+			fn eq(&self, other: &Integer) -> bool {
+				BigInt::from(*self) == other.0
+			}
+		}
+
+		impl PartialOrd<$t> for Integer {
+			fn partial_cmp(&self, other: &$t) -> Option<Ordering> {
+				self.0.partial_cmp(&BigInt::from(*other))
+			}
+		}
+
+		impl PartialOrd<Integer> for $t {
+			fn partial_cmp(&self, other: &Integer) -> Option<Ordering> {
+				BigInt::from(*self).partial_cmp(&other.0)
+			}
+		}
+
+		binary!($t, Add, add, AddAssign, add_assign);
+		binary!($t, Sub, sub, SubAssign, sub_assign);
+		binary!($t, Mul, mul, MulAssign, mul_assign);
+		binary!($t, Div, div, DivAssign, div_assign);
+		binary!($t, Rem, rem, RemAssign, rem_assign);
+	)*};
+}
+
+/// Operator `$op` with integers on both sides and with a primitive `$t` on either side.
+macro_rules! binary {
+	($t:ty, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident) => {
+		impl $op<$t> for Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: $t) -> Integer {
+				Integer($op::$f(self.0, BigInt::from(rhs)))
+			}
+		}
+
+		impl $op<$t> for &Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: $t) -> Integer {
+				Integer($op::$f(&self.0, BigInt::from(rhs)))
+			}
+		}
+
+		impl $op<Integer> for $t {
+			type Output = Integer;
+
+			fn $f(self, rhs: Integer) -> Integer {
+				Integer($op::$f(BigInt::from(self), rhs.0))
+			}
+		}
+
+		impl $op<&Integer> for $t {
+			type Output = Integer;
+
+			fn $f(self, rhs: &Integer) -> Integer {
+				Integer($op::$f(BigInt::from(self), &rhs.0))
+			}
+		}
+
+		impl $op_assign<$t> for Integer {
+			fn $f_assign(&mut self, rhs: $t) {
+				$op_assign::$f_assign(&mut self.0, BigInt::from(rhs));
+			}
+		}
+	};
+	($op:ident, $f:ident, $op_assign:ident, $f_assign:ident) => {
+		impl $op<Integer> for Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: Integer) -> Integer {
+				Integer($op::$f(self.0, rhs.0))
+			}
+		}
+
+		impl $op<&Integer> for Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: &Integer) -> Integer {
+				Integer($op::$f(self.0, &rhs.0))
+			}
+		}
+
+		impl $op<Integer> for &Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: Integer) -> Integer {
+				Integer($op::$f(&self.0, rhs.0))
+			}
+		}
+
+		impl $op<&Integer> for &Integer {
+			type Output = Integer;
+
+			fn $f(self, rhs: &Integer) -> Integer {
+				Integer($op::$f(&self.0, &rhs.0))
+			}
+		}
+
+		impl $op_assign<Integer> for Integer {
+			fn $f_assign(&mut self, rhs: Integer) {
+				$op_assign::$f_assign(&mut self.0, rhs.0);
+			}
+		}
+
+		impl $op_assign<&Integer> for Integer {
+			fn $f_assign(&mut self, rhs: &Integer) {
+				$op_assign::$f_assign(&mut self.0, &rhs.0);
+			}
+		}
+	};
+}
+
+/// Shifts by a primitive amount.
+macro_rules! shift {
+	($($t:ty),*) => {$(
+		impl Shl<$t> for Integer {
+			type Output = Integer;
+
+			fn shl(self, rhs: $t) -> Integer {
+				Integer(self.0 << rhs)
+			}
+		}
+
+		impl Shl<$t> for &Integer {
+			type Output = Integer;
+
+			fn shl(self, rhs: $t) -> Integer {
+				Integer(&self.0 << rhs)
+			}
+		}
+
+		impl Shr<$t> for Integer {
+			type Output = Integer;
+
+			fn shr(self, rhs: $t) -> Integer {
+				Integer(self.0 >> rhs)
+			}
+		}
+
+		impl Shr<$t> for &Integer {
+			type Output = Integer;
+
+			fn shr(self, rhs: $t) -> Integer {
+				Integer(&self.0 >> rhs)
+			}
+		}
+
+		impl ShlAssign<$t> for Integer {
+			fn shl_assign(&mut self, rhs: $t) {
+				self.0 <<= rhs;
+			}
+		}
+
+		impl ShrAssign<$t> for Integer {
+			fn shr_assign(&mut self, rhs: $t) {
+				self.0 >>= rhs;
+			}
+		}
+	)*};
+}
+
+binary!(Add, add, AddAssign, add_assign);
+binary!(Sub, sub, SubAssign, sub_assign);
+binary!(Mul, mul, MulAssign, mul_assign);
+binary!(Div, div, DivAssign, div_assign);
+binary!(Rem, rem, RemAssign, rem_assign);
+primitives!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+shift!(i32, u32, usize);
+
+impl Neg for Integer {
+	type Output = Integer;
+
+	fn neg(self) -> Integer {
+		Integer(-self.0)
+	}
+}
+
+impl Neg for &Integer {
+	type Output = Integer;
+
+	fn neg(self) -> Integer {
+		Integer(-&self.0)
+	}
+}
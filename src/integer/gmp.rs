@@ -0,0 +1,58 @@
+// GMP backend through `rug`.
+use super::BigInteger;
+use rug::integer::IsPrime;
+use rug::ops::Pow;
+use rug::Complete;
+
+pub use rug::Integer;
+
+pub type RandState = rug::rand::RandState<'static>;
+
+/// Mersenne twister seeded with `seed`.
+pub fn seeded_rand(seed: &Integer) -> RandState {
+	let mut rand = RandState::new_mersenne_twister();
+	rand.seed(seed);
+	rand
+}
+
+impl BigInteger for Integer {
+	fn modpow(&self, exp: &Self, m: &Self) -> Self {
+		Integer::from(self.pow_mod_ref(exp, m).expect("Exponent should be non-negative!"))
+	}
+
+	fn modinv(&self, m: &Self) -> Option<Self> {
+		self.invert_ref(m).map(Integer::from)
+	}
+
+	fn gcd_with(&self, other: &Self) -> Self {
+		self.gcd_ref(other).complete()
+	}
+
+	fn div_rem_euclid(&self, d: &Self) -> (Self, Self) {
+		self.div_rem_euc_ref(d).complete()
+	}
+
+	fn sample_below(&self, rand: &mut RandState) -> Self {
+		Integer::from(self.random_below_ref(rand))
+	}
+
+	fn isqrt(&self) -> Self {
+		self.sqrt_ref().complete()
+	}
+
+	fn abs_value(&self) -> Self {
+		self.abs_ref().complete()
+	}
+
+	fn pow_u(&self, e: u32) -> Self {
+		self.pow(e).complete()
+	}
+
+	fn is_probable_prime(&self, reps: u32) -> bool {
+		self.is_probably_prime(reps) != IsPrime::No
+	}
+
+	fn read_radix(s: &str, radix: u32) -> Option<Self> {
+		Integer::from_str_radix(s, radix as i32).ok()
+	}
+}
@@ -0,0 +1,185 @@
+// Pollard's kangaroo (lambda) method for discrete logs in a known interval.
+// Source: P. C. van Oorschot and M. J. Wiener, "Parallel Collision Search with Cryptanalytic
+//         Applications", Journal of Cryptology, 1999, section 5.
+use crate::budget::Budget;
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::parallel::is_distinguished;
+use crate::utils::gen_bigint_range;
+use crate::validate::validate;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Configuration of the kangaroo walks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KangarooConfig {
+	/// Number of distinct jump sizes, the jump is chosen from the encoding of the current point.
+	pub jumps: usize,
+	/// A point is distinguished if the hash of it has at least this many trailing zero bits.
+	pub distinguished_bits: u32,
+	/// Cancellation token and limits, an iteration moves both kangaroos.
+	pub budget: Budget,
+}
+
+impl Default for KangarooConfig {
+	fn default() -> Self {
+		KangarooConfig { jumps: 16, distinguished_bits: 4, budget: Budget::default() }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Herd {
+	Tame,
+	Wild,
+}
+
+/// A kangaroo at `base^distance` (tame) or `y * base^distance` (wild).
+struct Kangaroo<E> {
+	herd: Herd,
+	point: E,
+	distance: Integer,
+}
+
+/// Computes `x` in [lo, hi] for the DLP base**x mod p == y in O(sqrt(hi - lo)) group operations.
+/// Returns [`RhoError::IterationLimit`] if the walks with this seed do not collide,
+/// which also happens when `x` is not in the interval.
+/// # Arguments
+/// * `config` - Jump sizes and the distinguished point criterion.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `lo` - Lower bound of the interval.
+/// * `hi` - Upper bound of the interval.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+#[allow(clippy::too_many_arguments)]
+pub fn kangaroo(
+	config: &KangarooConfig,
+	seed: &Integer,
+	lo: &Integer,
+	hi: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	validate(base, y, p, n)?;
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	kangaroo_in(&group, config, seed, lo, hi, base, y)
+}
+
+/// Same as [`kangaroo`] in any [`Group`].
+pub fn kangaroo_in<G: Group>(
+	group: &G,
+	config: &KangarooConfig,
+	seed: &Integer,
+	lo: &Integer,
+	hi: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	if *lo < 0 || lo > hi {
+		return Err(RhoError::InvalidParameters(format!("invalid interval [{}, {}]", lo, hi)))
+	}
+	if config.jumps == 0 {
+		return Err(RhoError::InvalidParameters("kangaroo needs at least one jump size".into()))
+	}
+	let width = Integer::from(hi - lo);
+	// The mean jump sqrt(width)/2 is optimal for one tame and one wild kangaroo.
+	let mean = (width.isqrt() / 2u32).max(Integer::from(1));
+	let mut rand = seeded_rand(seed);
+	let one = Integer::from(1);
+	let jump_max = Integer::from(&mean * 2) + 1u32;
+	let sizes: Vec<Integer> =
+		(0..config.jumps).map(|_| gen_bigint_range(&mut rand, &one, &jump_max)).collect();
+	let jumps: Vec<G::Element> = sizes.iter().map(|s| group.pow(base, s)).collect();
+	// The tame kangaroo starts in the upper half of the interval,
+	// the wild one up to half the width behind or ahead of it.
+	let mid = Integer::from(&width / 2u32) + lo;
+	let tame_start = gen_bigint_range(&mut rand, &mid, &(Integer::from(hi) + 1u32));
+	let wild_start =
+		gen_bigint_range(&mut rand, &Integer::new(), &(Integer::from(&mid - lo) + 1u32));
+	let mut herd = [
+		Kangaroo { herd: Herd::Tame, point: group.pow(base, &tame_start), distance: tame_start },
+		Kangaroo {
+			herd: Herd::Wild,
+			point: group.op(y, &group.pow(base, &wild_start)),
+			distance: wild_start,
+		},
+	];
+	// The rear kangaroo needs about width/mean jumps to catch up with the front one,
+	// then about mean jumps to land on its trail and 2^bits more to reach a distinguished point.
+	let limit = (Integer::from(&width / &mean) + &mean) * 4u32
+		+ (Integer::from(1) << config.distinguished_bits.min(40)) * 4u32;
+	let mut table: HashMap<Integer, (Herd, Integer)> = HashMap::new();
+	let partitions = config.jumps as u32;
+	let start = Instant::now();
+	let mut i = 0u64;
+	while limit > i {
+		config.budget.check(i).map_err(|reason| {
+			let report = SolveReport {
+				iterations: i,
+				group_operations: i * 2,
+				elapsed: start.elapsed(),
+				..Default::default()
+			};
+			reason.into_error(report)
+		})?;
+		for kangaroo in herd.iter_mut() {
+			let j = group.encode(&kangaroo.point).mod_u(partitions) as usize;
+			kangaroo.point = group.op(&kangaroo.point, &jumps[j]);
+			kangaroo.distance += &sizes[j];
+			let key = group.encode(&kangaroo.point);
+			if !is_distinguished(&key, config.distinguished_bits) {
+				continue
+			}
+			match table.get(&key) {
+				Some((other, distance)) if *other != kangaroo.herd => {
+					// base^tame = y * base^wild  ==>  x = tame - wild
+					let x = match kangaroo.herd {
+						Herd::Tame => Integer::from(&kangaroo.distance - distance),
+						Herd::Wild => Integer::from(distance - &kangaroo.distance),
+					};
+					if lo <= &x && &x <= hi && group.equal(&group.pow(base, &x), y) {
+						return Ok(x)
+					}
+				},
+				Some(_) => {},
+				None => {
+					table.insert(key, (kangaroo.herd, kangaroo.distance.clone()));
+				},
+			}
+		}
+		i += 1;
+	}
+	Err(RhoError::IterationLimit)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_kangaroo() {
+		// 3 generates Z_p^* for p = 2 * 3^3 * 5 * 17179869433 + 1.
+		let p = Integer::from(4638564746911u64);
+		let n = Integer::from(&p - 1);
+		let base = Integer::from(3);
+		let lo = Integer::from(1_000_000_000u64);
+		let hi = Integer::from(1_001_000_000u64);
+		let config = KangarooConfig::default();
+		for key in [1_000_000_000u64, 1_000_123_457, 1_000_999_999, 1_001_000_000] {
+			let key = Integer::from(key);
+			let y = base.modpow(&key, &p);
+			let res = (0..10)
+				.find_map(|seed| {
+					kangaroo(&config, &Integer::from(seed), &lo, &hi, &base, &y, &p, &n).ok()
+				})
+				.expect("Kangaroo should find the key!");
+			assert_eq!(res, key);
+		}
+		let y = base.modpow(&lo, &p);
+		assert_eq!(kangaroo(&config, &Integer::new(), &lo, &lo, &base, &y, &p, &n), Ok(lo));
+	}
+}
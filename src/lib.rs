@@ -1,50 +1,79 @@
+// Arithmetic on rug references yields incomplete values completed by `Integer::from`, which is
+// the identity on the integers of the num-bigint backend.
+#![cfg_attr(not(feature = "rug"), allow(clippy::useless_conversion, clippy::cmp_owned))]
 mod utils;
+pub mod batch;
+pub mod bsgs;
+pub mod budget;
+pub mod checkpoint;
 pub mod generic;
+pub mod group;
+pub mod index_calculus;
+pub mod integer;
+pub mod ec;
+pub mod elgamal;
+pub mod factor;
+pub mod kangaroo;
+pub mod montgomery;
+pub mod observer;
+pub mod parallel;
+pub mod pohlig_hellman;
+pub mod small;
+pub mod validate;
+pub mod walk;
 // import local package.
+use crate::integer::{seeded_rand, BigInteger, Integer};
 use crate::utils::gen_bigint_range;
-// use external crates.
-use rug::{rand::RandState, Complete, Integer};
-use std::fmt;
+use std::time::Instant;
 
-use crate::generic::{MapResult, MappingError};
-/// Source: Handbook of Applied Cryptography chapter-3
-///         http://cacr.uwaterloo.ca/hac/about/chap3.pdf
-/// rust programming by yangfh2004, January 2022
+use crate::bsgs::{bsgs_report, bsgs_report_in, BsgsConfig};
+use crate::budget::Budget;
+use crate::checkpoint::{Checkpoints, WalkState};
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::montgomery::Montgomery;
+use crate::observer::{notify, NoObserver, Observer, Offset, Progress};
+use crate::small::SmallGroup;
+use crate::validate::validate;
+use crate::walk::{Escape, Walk, Walker};
+// Source: Handbook of Applied Cryptography chapter-3
+//         http://cacr.uwaterloo.ca/hac/about/chap3.pdf
+// rust programming by yangfh2004, January 2022
 
 const BIG_INT_0: Integer = Integer::ZERO;
 
-impl fmt::Display for MappingError {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "Error in mapping functions")
+fn func_f<G: Group>(
+	group: &G,
+	x_i: &G::Element,
+	key: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> G::Element {
+	match key.mod_u(3) {
+		0 => group.op(x_i, x_i),
+		1 => group.op(base, x_i),
+		_ => group.op(y, x_i),
 	}
 }
 
-fn func_f(x_i: &Integer, base: &Integer, y: &Integer, p: &Integer) -> MapResult<Integer> {
+fn func_g(a: &Integer, n: &Integer, x_i: &Integer) -> Integer {
 	match x_i.mod_u(3) {
-		0 => Ok(Integer::from(x_i.pow_mod_ref(&Integer::from(2), p).unwrap())),
-		1 => Ok(Integer::from(base * x_i).div_rem_euc_ref(p).complete().1),
-		2 => Ok(Integer::from(y * x_i).div_rem_euc_ref(p).complete().1),
-		_ => Err(MappingError),
+		0 => Integer::from(a * 2).rem_euclid(n),
+		1 => Integer::from(a + 1).rem_euclid(n),
+		_ => a.clone(),
 	}
 }
 
-fn func_g(a: &Integer, n: &Integer, x_i: &Integer) -> MapResult<Integer> {
+fn func_h(b: &Integer, n: &Integer, x_i: &Integer) -> Integer {
 	match x_i.mod_u(3) {
-		0 => Ok(Integer::from(a * 2).div_rem_euc_ref(n).complete().1),
-		1 => Ok(Integer::from(a + 1).div_rem_euc_ref(n).complete().1),
-		2 => Ok(a.clone()),
-		_ => Err(MappingError),
+		0 => Integer::from(b * 2).rem_euclid(n),
+		1 => b.clone(),
+		_ => Integer::from(b + 1).rem_euclid(n),
 	}
 }
 
-fn func_h(b: &Integer, n: &Integer, x_i: &Integer) -> MapResult<Integer> {
-	match x_i.mod_u(3) {
-		0 => Ok(Integer::from(b * 2).div_rem_euc_ref(n).complete().1),
-		1 => Ok(b.clone()),
-		2 => Ok(Integer::from(b + 1).div_rem_euc_ref(n).complete().1),
-		_ => Err(MappingError),
-	}
-}
+/// At most this many candidate solutions of a collision are checked against `y`.
+pub(crate) const MAX_CANDIDATES: u32 = 1 << 16;
 
 /// The equation to solve the private key from intermediate results of pollard rho algorithm.
 /// If x_i == x_2i is True
@@ -55,50 +84,142 @@ fn func_h(b: &Integer, n: &Integer, x_i: &Integer) -> MapResult<Integer> {
 ///     r = (b1 - b2) mod_floor (n)
 ///     if GCD(r, n) == 1 then,
 ///     ==> x = (r^(-1))*(a2 - a1)                      (mod n)
-/// If `n` is not a prime number this algorithm will not be able to
-/// solve the DLP, because GCD(r, n) != 1 then and one will have to
-/// write an implementation to solve the equation:
+/// If `n` is not a prime number GCD(r, n) may be greater than 1, then the equation
 ///     (b1 - b2)*x = (a2 - a1) (mod n)
-/// This equation will have multiple solutions out of which only one
-/// will be the actual solution
-
+/// has multiple solutions out of which only one will be the actual solution.
+/// This function returns the smallest of them, see [`eqs_solutions`] for all of them
+/// and [`eqs_solvers_in`] to pick the actual solution.
 pub fn eqs_solvers(
 	a1: &Integer,
 	b1: &Integer,
 	a2: &Integer,
 	b2: &Integer,
 	n: &Integer,
-) -> Option<Integer> {
-	let r = Integer::from(b1 - b2).div_rem_euc_ref(n).complete().1;
+) -> RhoResult<Integer> {
+	if Integer::from(b1 - b2).is_divisible(n) {
+		return Err(RhoError::DegenerateCollision)
+	}
+	eqs_solutions(a1, b1, a2, b2, n).next().ok_or(RhoError::NonInvertibleCollision)
+}
+
+/// All solutions in [0, n) of the collision equation
+///     (b1 - b2)*x = (a2 - a1) (mod n)
+/// in increasing order. With d = GCD(r, n) for r = (b1 - b2) mod_floor (n),
+/// there is no solution unless d divides (a2 - a1), otherwise there are exactly d of them:
+///     x_0 = (r/d)^(-1) * ((a2 - a1)/d)                (mod n/d)
+///     x_k = x_0 + k*(n/d)                             for k = 0, 1, ..., d - 1
+/// There is no solution either for the degenerate collision r == 0.
+pub fn eqs_solutions(
+	a1: &Integer,
+	b1: &Integer,
+	a2: &Integer,
+	b2: &Integer,
+	n: &Integer,
+) -> EqsSolutions {
+	let none =
+		EqsSolutions { next: Integer::new(), step: Integer::new(), remaining: Integer::new() };
+	let r = Integer::from(b1 - b2).rem_euclid(n);
 	if r == 0 {
-		None
-	} else {
-		match r.invert_ref(n) {
-			Some(inv) => {
-				let res_inv = Integer::from(inv);
-				let dif = Integer::from(a2 - a1);
-				Some(Integer::from(res_inv * dif).div_rem_euc_ref(n).complete().1)
-			},
-			None => {
-				let div = r.gcd(n);
-				// div is the first value of (g, x, y) as a result of gcd of r and n.
-				let res_l = Integer::from(b1 - b2) / &div;
-				let res_r = Integer::from(a2 - a2) / &div;
-				let p1 = Integer::from(n / &div);
-				match res_l.invert(&p1) {
-					Ok(res_inv) =>
-						Some(Integer::from(res_inv * res_r).div_rem_euc_ref(&p1).complete().1),
-					Err(_) => None,
-				}
-			},
+		return none
+	}
+	let dif = Integer::from(a2 - a1).rem_euclid(n);
+	let div = r.gcd_with(n);
+	if !dif.is_divisible(&div) {
+		return none
+	}
+	let p1 = Integer::from(n / &div);
+	let res_l = r / &div;
+	let res_r = dif / &div;
+	match res_l.modinv(&p1) {
+		Some(res_inv) => {
+			let next = (res_inv * res_r).rem_euclid(&p1);
+			EqsSolutions { next, step: p1, remaining: div }
+		},
+		None => none,
+	}
+}
+
+/// Iterator over the solutions of the collision equation, see [`eqs_solutions`].
+#[derive(Debug, Clone)]
+pub struct EqsSolutions {
+	next: Integer,
+	step: Integer,
+	remaining: Integer,
+}
+
+impl Iterator for EqsSolutions {
+	type Item = Integer;
+
+	fn next(&mut self) -> Option<Integer> {
+		if self.remaining == 0 {
+			return None
 		}
+		let res = self.next.clone();
+		self.next += &self.step;
+		self.remaining -= 1;
+		Some(res)
 	}
 }
 
+/// Solve the collision equation in `group` and return the solution satisfying base^x == y.
+/// Collisions with more than `MAX_CANDIDATES` solutions are given up.
+pub fn eqs_solvers_in<G: Group>(
+	group: &G,
+	base: &G::Element,
+	y: &G::Element,
+	a1: &Integer,
+	b1: &Integer,
+	a2: &Integer,
+	b2: &Integer,
+) -> RhoResult<Integer> {
+	let n = group.order();
+	if Integer::from(b1 - b2).is_divisible(n) {
+		return Err(RhoError::DegenerateCollision)
+	}
+	let solutions = eqs_solutions(a1, b1, a2, b2, n);
+	if solutions.remaining > MAX_CANDIDATES {
+		return Err(RhoError::NonInvertibleCollision)
+	}
+	solutions
+		.into_iter()
+		.find(|x| group.equal(&group.pow(base, x), y))
+		.ok_or(RhoError::NonInvertibleCollision)
+}
+
+/// Cycle-finding strategy used to detect the collision of the pseudo-random walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CycleDetection {
+	/// Floyd's tortoise-and-hare, costs three walk steps per iteration.
+	#[default]
+	Floyd,
+	/// Brent's power-of-two search, costs a single walk step per iteration.
+	Brent,
+}
+
+/// Options of the rho walk.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RhoConfig {
+	/// Cycle detection algorithm used to find the collision of the walk.
+	pub cycle: CycleDetection,
+	/// Iteration function of the walk.
+	pub walk: Walk,
+	/// Walk the cycle again after the collision to measure the exact tail length and
+	/// cycle length for the [`SolveReport`], which costs about as many steps once more.
+	/// Skipped if the walk escaped from a fruitless cycle of the negation map.
+	pub measure_cycle: bool,
+	/// Walk on the classes {x, x^-1} instead of the group elements, which needs about
+	/// sqrt(2) times fewer steps in groups with a cheap [`Group::inverse`] such as
+	/// elliptic curves. Fruitless cycles of the walk are escaped by squaring.
+	pub negation_map: bool,
+	/// Cancellation token and limits, the iterations are counted over all restarts.
+	pub budget: Budget,
+}
+
 /// Refer to section 3.6.3 of Handbook of Applied Cryptography
 /// Computes `x` = a mod n for the DLP base**x mod p == y
 /// in the Group G = {0, 1, 2, ..., n}
 /// given that order `n` is a prime number.
+/// A composite order is supported, but [`pohlig_hellman::pohlig_hellman`] is much faster then.
 /// Since the RNG may not be thread-safe, it would be better to generate a RNG for each instance,
 /// which has only small impact on overall performance.
 /// # Arguments
@@ -106,54 +227,360 @@ pub fn eqs_solvers(
 /// * `base` - Generator of the group.
 /// * `y` - Result of base**x mod p.
 /// * `p` - Group over which DLP is generated.
-/// * `n` - Order of the group generated by `base`. Should be prime for this implementation.
+/// * `n` - Order of the group generated by `base`.
 pub fn pollard_rho(
 	seed: &Integer,
 	base: &Integer,
 	y: &Integer,
 	p: &Integer,
 	n: &Integer,
-) -> Option<Integer> {
+) -> RhoResult<Integer> {
+	pollard_rho_with(&RhoConfig::default(), seed, base, y, p, n)
+}
+
+/// Same as [`pollard_rho`] but with a selectable cycle-finding strategy and walk.
+/// Runs on native integers whenever `p` fits into a u64, see [`small::SmallGroup`], and on
+/// Montgomery residues for any other odd `p`, see [`montgomery::Montgomery`].
+/// # Arguments
+/// * `config` - Cycle detection algorithm and iteration function of the walk.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn pollard_rho_with(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	pollard_rho_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Computes `x` = a mod n for the DLP base**x == y in any [`Group`] of order `n`.
+/// # Arguments
+/// * `group` - Group over which DLP is generated.
+/// * `config` - Cycle detection algorithm and iteration function of the walk.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x.
+pub fn pollard_rho_in<G: Group>(
+	group: &G,
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	pollard_rho_report_in(group, config, seed, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`pollard_rho_with`] but also returns the statistics of the walk.
+pub fn pollard_rho_report(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	if let Some(small) = small_group(config, p, n) {
+		let (base, y) = (base.to_u64_wrapping(), y.to_u64_wrapping());
+		return small
+			.pollard_rho_report(config, seed, base, y)
+			.map(|(key, report)| (key.into(), report))
+	}
+	if let Some(ctx) = montgomery(config, p) {
+		return ctx.pollard_rho_report(config, seed, base, y, n)
+	}
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	pollard_rho_report_in(&group, config, seed, base, y)
+}
+
+/// The native-integer group the walks over Z_p^* take whenever `p` fits into a u64,
+/// unless the cycle is to be measured, which only the walk on [`Integer`]s does.
+/// The observed walks always run on [`Integer`]s too, the native and Montgomery walks
+/// have no observer.
+fn small_group(config: &RhoConfig, p: &Integer, n: &Integer) -> Option<SmallGroup> {
+	SmallGroup::new(p, n).filter(|_| !config.measure_cycle)
+}
+
+/// The Montgomery residues the walks over Z_p^* take for odd `p` beyond a u64,
+/// with the same exception as [`small_group`].
+fn montgomery(config: &RhoConfig, p: &Integer) -> Option<Montgomery> {
+	Montgomery::new(p).filter(|_| !config.measure_cycle)
+}
+
+/// Same as [`pollard_rho_in`] but also returns the statistics of the walk.
+pub fn pollard_rho_report_in<G: Group>(
+	group: &G,
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)> {
+	let (res, report) = rho_attempt(
+		group,
+		config,
+		seed,
+		base,
+		y,
+		None,
+		&mut Checkpoints::disabled(),
+		&mut NoObserver,
+	);
+	res.map(|key| (key, report))
+}
+
+/// Same as [`pollard_rho_with`] but calls `observer` while the walk runs.
+/// The walk always runs on [`Integer`]s, never on native integers or Montgomery residues.
+pub fn pollard_rho_observed<O: Observer>(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+	observer: &mut O,
+) -> RhoResult<Integer> {
+	validate(base, y, p, n)?;
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	pollard_rho_observed_in(&group, config, seed, base, y, observer)
+}
+
+/// Same as [`pollard_rho_in`] but calls `observer` while the walk runs.
+pub fn pollard_rho_observed_in<G: Group, O: Observer>(
+	group: &G,
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	observer: &mut O,
+) -> RhoResult<Integer> {
+	rho_attempt(group, config, seed, base, y, None, &mut Checkpoints::disabled(), observer).0
+}
+
+/// A single walk from the seed, or from the snapshot `resume` of such a walk.
+/// The report is filled in whether it succeeds or not.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn rho_attempt<G: Group, O: Observer>(
+	group: &G,
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	resume: Option<&WalkState>,
+	checkpoints: &mut Checkpoints,
+	observer: &mut O,
+) -> (RhoResult<Integer>, SolveReport) {
+	let start = Instant::now();
+	let mut report = SolveReport::default();
+	let n = group.order();
+	if let Err(err) = check_order(n) {
+		return (Err(err), report)
+	}
 	// Use mersenne twister algorithm to generate random numbers
-	let mut rand = RandState::new_mersenne_twister();
-	rand.seed(seed);
-	let mut a_i: Integer = gen_bigint_range(&mut rand, &BIG_INT_0, n);
-	let mut b_i: Integer = gen_bigint_range(&mut rand, &BIG_INT_0, n);
-	let mut a_2i = a_i.clone();
-	let mut b_2i = b_i.clone();
-	let x_i_base = Integer::from(base.pow_mod_ref(&a_i, &p)?);
-	let x_i_y = Integer::from(y.pow_mod_ref(&b_i, &p)?);
-	let mut x_i = Integer::from(x_i_base * x_i_y).div_rem_euc_ref(p).complete().1;
-	let mut x_2i = x_i.clone();
-	let mut i = BIG_INT_0.clone();
-	let mut xm_2i: Integer;
-	let mut am_2i: Integer;
-	let mut bm_2i: Integer;
-	while &i < n {
+	let mut rand = seeded_rand(seed);
+	let a_i: Integer = gen_bigint_range(&mut rand, &BIG_INT_0, n);
+	let b_i: Integer = gen_bigint_range(&mut rand, &BIG_INT_0, n);
+	let walker = match Walker::new(config.walk, config.negation_map, &mut rand, group, base, y) {
+		Ok(walker) => walker,
+		Err(err) => return (Err(err), report),
+	};
+	let (a_i, b_i) = walker.enter(a_i, b_i);
+	let state = WalkState::start(&walker, config, seed, a_i.clone(), b_i.clone());
+	let state = match resume {
+		Some(resume) if !resume.matches(&state) => {
+			let reason = "checkpoint belongs to another walk".into();
+			return (Err(RhoError::InvalidParameters(reason)), report)
+		},
+		Some(resume) => resume.clone(),
+		None => state,
+	};
+	let budget = &config.budget;
+	let mut res = match config.cycle {
+		CycleDetection::Floyd => floyd(&walker, state, checkpoints, budget, observer, &mut report),
+		CycleDetection::Brent => brent(&walker, state, checkpoints, budget, observer, &mut report),
+	};
+	if config.measure_cycle && report.collision.is_some() && report.fruitless_cycles == 0 {
+		let x_i = walker.start(&a_i, &b_i);
+		measure_cycle(&walker, (x_i, a_i, b_i), &mut report);
+	}
+	report.elapsed = start.elapsed();
+	if let Err(RhoError::Cancelled(_, partial)) = &mut res {
+		**partial = report.clone();
+	}
+	(res, report)
+}
+
+/// The walks draw their exponents from [0, n), which needs at least two residues.
+pub(crate) fn check_order(n: &Integer) -> RhoResult<()> {
+	if *n < 2 {
+		return Err(RhoError::InvalidParameters(format!("group order {} is less than 2", n)))
+	}
+	Ok(())
+}
+
+fn floyd<G: Group, O: Observer>(
+	walker: &Walker<G>,
+	mut state: WalkState,
+	checkpoints: &mut Checkpoints,
+	budget: &Budget,
+	observer: &mut O,
+	report: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let group = walker.group();
+	let n = group.order();
+	let mut x_i = walker.start(&state.a_i, &state.b_i);
+	let mut x_2i = walker.start(&state.a_j, &state.b_j);
+	report.iterations = state.iteration;
+	report.group_operations = state.iteration * 3;
+	while *n > state.iteration {
+		budget
+			.check(state.iteration)
+			.map_err(|reason| reason.into_error(SolveReport::default()))?;
 		// Single Step calculations.
-		a_i = func_g(&a_i, n, &x_i).expect("Mapping function g has error!");
-		b_i = func_h(&b_i, n, &x_i).expect("Mapping function h has error!");
-		x_i = func_f(&x_i, base, y, p).expect("Mapping function f has error!");
+		(x_i, state.a_i, state.b_i) = walker.step(&x_i, &state.a_i, &state.b_i);
 		// Double Step calculations
-		xm_2i = func_f(&x_2i, base, y, p)
-			.expect("Mapping function f has error in the intermediate step!");
-		am_2i = func_g(&a_2i, n, &x_2i)
-			.expect("Mapping function g has error in the intermediate step!");
-		a_2i = func_g(&am_2i, n, &xm_2i).expect("Mapping function g has error in the final step!");
-		bm_2i = func_h(&b_2i, n, &x_2i)
-			.expect("Mapping function h has error in the intermediate step!");
-		b_2i = func_h(&bm_2i, n, &xm_2i).expect("Mapping function h has error in the final step!");
-		x_2i = func_f(&xm_2i, base, y, p).expect("Mapping function f has error in the final step!");
-		if x_i == x_2i {
-			return eqs_solvers(&a_i, &b_i, &a_2i, &b_2i, n)
-		} else {
-			i += 1;
+		let (xm_2i, am_2i, bm_2i) = walker.step(&x_2i, &state.a_j, &state.b_j);
+		(x_2i, state.a_j, state.b_j) = walker.step(&xm_2i, &am_2i, &bm_2i);
+		state.iteration += 1;
+		report.iterations += 1;
+		report.group_operations += 3;
+		notify(observer, state.iteration, &state.seed, 0);
+		if group.equal(&x_i, &x_2i) {
+			if !walker.is_fruitless(&state.a_i, &state.b_i, &state.a_j, &state.b_j) {
+				report.collision = Some((report.iterations, report.iterations * 2));
+				return walker.solve(&state.a_i, &state.b_i, &state.a_j, &state.b_j)
+			}
+			// both walks continue from the same point outside of the fruitless cycle.
+			match walker.escape(&mut state.escapes, &x_i, &state.a_i, &state.b_i, report)? {
+				Escape::Continue(x, a, b) => {
+					(x_2i, state.a_j, state.b_j) = (x.clone(), a.clone(), b.clone());
+					(x_i, state.a_i, state.b_i) = (x, a, b);
+				},
+				Escape::Collision(a1, b1, a2, b2) => {
+					report.collision = Some((report.iterations, report.iterations * 2));
+					return walker.solve(&a1, &b1, &a2, &b2)
+				},
+			}
 		}
+		checkpoints.offer(&state)?;
 	}
-	None
+	Err(RhoError::IterationLimit)
 }
 
-/// try to use pollard rho algorithm solve DLP problem with limited number of iterations.
+/// Brent's variant keeps the walk point saved at the last power of two and moves a single
+/// walk forward, so each iteration costs one group operation instead of three.
+/// The tail plus cycle of the walk is at most `n`, hence a collision shows up within `3n` steps.
+fn brent<G: Group, O: Observer>(
+	walker: &Walker<G>,
+	mut state: WalkState,
+	checkpoints: &mut Checkpoints,
+	budget: &Budget,
+	observer: &mut O,
+	report: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let group = walker.group();
+	let n = group.order();
+	let mut x_i = walker.start(&state.a_i, &state.b_i);
+	let mut x_s = walker.start(&state.a_j, &state.b_j);
+	let limit = Integer::from(n * 3);
+	report.iterations = state.iteration;
+	report.group_operations = state.iteration;
+	while limit > state.iteration {
+		budget
+			.check(state.iteration)
+			.map_err(|reason| reason.into_error(SolveReport::default()))?;
+		(x_i, state.a_i, state.b_i) = walker.step(&x_i, &state.a_i, &state.b_i);
+		state.lam += 1;
+		state.iteration += 1;
+		report.iterations += 1;
+		report.group_operations += 1;
+		notify(observer, state.iteration, &state.seed, 0);
+		if group.equal(&x_i, &x_s) {
+			if !walker.is_fruitless(&state.a_j, &state.b_j, &state.a_i, &state.b_i) {
+				// the saved point lies on the cycle, so lam is exactly the cycle length.
+				report.collision = Some((report.iterations - state.lam, report.iterations));
+				report.cycle = Some(state.lam);
+				return walker.solve(&state.a_j, &state.b_j, &state.a_i, &state.b_i)
+			}
+			// start the search over from the point outside of the fruitless cycle.
+			match walker.escape(&mut state.escapes, &x_i, &state.a_i, &state.b_i, report)? {
+				Escape::Continue(x, a, b) => {
+					(x_s, state.a_j, state.b_j) = (x.clone(), a.clone(), b.clone());
+					(x_i, state.a_i, state.b_i) = (x, a, b);
+					state.power = 1;
+					state.lam = 0;
+				},
+				Escape::Collision(a1, b1, a2, b2) => {
+					report.collision = Some((report.iterations - state.lam, report.iterations));
+					return walker.solve(&a1, &b1, &a2, &b2)
+				},
+			}
+		}
+		if state.lam == state.power {
+			// move the saved point to the current position and double the search window.
+			x_s = x_i.clone();
+			state.a_j = state.a_i.clone();
+			state.b_j = state.b_i.clone();
+			state.power *= 2;
+			state.lam = 0;
+		}
+		checkpoints.offer(&state)?;
+	}
+	Err(RhoError::IterationLimit)
+}
+
+/// Measures the exact tail length and cycle length of the walk from `start`
+/// once the cycle detection has found the collision recorded in `report`.
+fn measure_cycle<G: Group>(
+	walker: &Walker<G>,
+	start: (G::Element, Integer, Integer),
+	report: &mut SolveReport,
+) {
+	let group = walker.group();
+	let advance = |(x, a, b): &(G::Element, Integer, Integer), steps: u64| {
+		let mut state = (x.clone(), a.clone(), b.clone());
+		for _ in 0..steps {
+			state = walker.step(&state.0, &state.1, &state.2);
+		}
+		state
+	};
+	let (_, j) = report.collision.unwrap();
+	let cycle = match report.cycle {
+		Some(cycle) => cycle,
+		None => {
+			// the later colliding point lies on the cycle, walk around it once.
+			let on_cycle = advance(&start, j);
+			let mut x = advance(&on_cycle, 1);
+			let mut cycle = 1;
+			while !group.equal(&x.0, &on_cycle.0) {
+				x = advance(&x, 1);
+				cycle += 1;
+			}
+			report.group_operations += j + cycle;
+			cycle
+		},
+	};
+	// a walker `cycle` steps ahead meets the other one at the first point of the cycle.
+	let mut behind = start.clone();
+	let mut ahead = advance(&start, cycle);
+	let mut tail = 0;
+	while !group.equal(&behind.0, &ahead.0) {
+		behind = advance(&behind, 1);
+		ahead = advance(&ahead, 1);
+		tail += 1;
+	}
+	report.group_operations += cycle + 2 * tail;
+	report.tail = Some(tail);
+	report.cycle = Some(cycle);
+}
+
+/// try to use pollard rho algorithm solve DLP problem with limited number of restarts.
+/// Returns the error of the last trial if the key cannot be found after all trials.
 pub fn try_pollard_rho(
 	limit: usize,
 	seed: &Integer,
@@ -161,23 +588,270 @@ pub fn try_pollard_rho(
 	y: &Integer,
 	p: &Integer,
 	n: &Integer,
-) -> Integer {
+) -> RhoResult<Integer> {
+	try_pollard_rho_with(&RhoConfig::default(), limit, seed, base, y, p, n)
+}
+
+/// Same as [`try_pollard_rho`] but with a selectable cycle-finding strategy and walk.
+pub fn try_pollard_rho_with(
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	try_pollard_rho_report(config, limit, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`try_pollard_rho_with`] in any [`Group`] of order `n`.
+pub fn try_pollard_rho_in<G: Group>(
+	group: &G,
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	try_pollard_rho_report_in(group, config, limit, seed, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`try_pollard_rho_with`] but also returns the statistics summed over all attempts.
+pub fn try_pollard_rho_report(
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	if let Some(small) = small_group(config, p, n) {
+		let (base, y) = (base.to_u64_wrapping(), y.to_u64_wrapping());
+		return small
+			.try_pollard_rho_report(config, limit, seed, base, y)
+			.map(|(key, report)| (key.into(), report))
+	}
+	if let Some(ctx) = montgomery(config, p) {
+		return ctx.try_pollard_rho_report(config, limit, seed, base, y, n)
+	}
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	try_pollard_rho_report_in(&group, config, limit, seed, base, y)
+}
+
+/// Same as [`try_pollard_rho_in`] but also returns the statistics summed over all attempts.
+pub fn try_pollard_rho_report_in<G: Group>(
+	group: &G,
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)> {
+	try_rho(config, limit, seed, &mut NoObserver, |config, seed, observer| {
+		rho_attempt(group, config, seed, base, y, None, &mut Checkpoints::disabled(), observer)
+	})
+}
+
+/// Same as [`try_pollard_rho_with`] but calls `observer` while the walks run and on
+/// every restart. The walks always run on [`Integer`]s, never on native integers or
+/// Montgomery residues.
+#[allow(clippy::too_many_arguments)]
+pub fn try_pollard_rho_observed<O: Observer>(
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+	observer: &mut O,
+) -> RhoResult<Integer> {
+	validate(base, y, p, n)?;
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	try_pollard_rho_observed_in(&group, config, limit, seed, base, y, observer)
+}
+
+/// Same as [`try_pollard_rho_in`] but calls `observer` while the walks run and on
+/// every restart.
+pub fn try_pollard_rho_observed_in<G: Group, O: Observer>(
+	group: &G,
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	observer: &mut O,
+) -> RhoResult<Integer> {
+	try_rho(config, limit, seed, observer, |config, seed, observer| {
+		rho_attempt(group, config, seed, base, y, None, &mut Checkpoints::disabled(), observer)
+	})
+	.map(|(key, _)| key)
+}
+
+/// Runs `attempt` with the seeds `seed`, `seed + 1`, ... until one of them finds the key
+/// or `limit` restarts have failed.
+pub(crate) fn try_rho<O, F>(
+	config: &RhoConfig,
+	limit: usize,
+	seed: &Integer,
+	observer: &mut O,
+	mut attempt: F,
+) -> RhoResult<(Integer, SolveReport)>
+where
+	O: Observer,
+	F: FnMut(&RhoConfig, &Integer, &mut Offset<O>) -> (RhoResult<Integer>, SolveReport),
+{
+	let start = Instant::now();
 	let mut loop_count = 0;
 	let mut current_seed = seed.clone();
+	let mut total = SolveReport::default();
 	loop {
-		if let Some(key) = pollard_rho(&current_seed, &base, &y, &p, &n) {
-			break key
-		} else if loop_count < limit {
-			// if cannot find solution with current seed, mutate the seed and try again.
-			current_seed += 1;
-			loop_count += 1;
-		} else {
-			// if cannot find the key after all trials, return zero.
-			break Integer::ZERO
+		// the budget covers all attempts, so each one only gets what is left of it.
+		let attempt_config =
+			RhoConfig { budget: config.budget.spent(total.iterations), ..config.clone() };
+		let (res, report) = attempt(
+			&attempt_config,
+			&current_seed,
+			&mut Offset { observer: &mut *observer, iterations: total.iterations },
+		);
+		total.iterations += report.iterations;
+		total.group_operations += report.group_operations;
+		total.fruitless_cycles += report.fruitless_cycles;
+		match res {
+			Ok(key) => {
+				total.collision = report.collision;
+				total.tail = report.tail;
+				total.cycle = report.cycle;
+				total.restarts = loop_count;
+				total.elapsed = start.elapsed();
+				break Ok((key, total))
+			},
+			Err(RhoError::Cancelled(reason, _)) => {
+				total.restarts = loop_count;
+				total.elapsed = start.elapsed();
+				break Err(reason.into_error(total))
+			},
+			Err(err @ (RhoError::InvalidParameters(_) | RhoError::Checkpoint(_))) => break Err(err),
+			Err(err) =>
+				if loop_count < limit {
+					// if cannot find solution with current seed, mutate the seed and try again.
+					current_seed += 1;
+					loop_count += 1;
+					if O::ENABLED {
+						let iterations = total.iterations;
+						let progress =
+							Progress { iterations, seed: &current_seed, distinguished_points: 0 };
+						observer.on_restart(&progress);
+					}
+				} else {
+					break Err(err)
+				},
 		}
 	}
 }
 
+/// Settings of [`discrete_log`], which runs BSGS whenever its balanced table fits into
+/// the memory bound and falls back to pollard rho with restarts otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverConfig {
+	/// Memory bound of the BSGS table, also deciding between BSGS and rho.
+	pub bsgs: BsgsConfig,
+	/// Cycle detection algorithm and iteration function of the rho walk.
+	pub rho: RhoConfig,
+	/// Number of times rho is restarted with a new seed.
+	pub restarts: usize,
+	/// Cancellation token and limits of the chosen solver, they replace the budgets
+	/// of `bsgs` and `rho`.
+	pub budget: Budget,
+}
+
+impl SolverConfig {
+	/// The settings of BSGS and rho with the budget of this config.
+	fn solvers(&self) -> (BsgsConfig, RhoConfig) {
+		let bsgs = BsgsConfig { budget: self.budget.clone(), ..self.bsgs.clone() };
+		let rho = RhoConfig { budget: self.budget.clone(), ..self.rho.clone() };
+		(bsgs, rho)
+	}
+}
+
+impl Default for SolverConfig {
+	fn default() -> Self {
+		SolverConfig {
+			bsgs: BsgsConfig::default(),
+			rho: RhoConfig::default(),
+			restarts: 10,
+			budget: Budget::default(),
+		}
+	}
+}
+
+/// Computes `x` = a mod n for the DLP base**x mod p == y with the solver best suited
+/// to the size of `n`, see [`SolverConfig`].
+/// # Arguments
+/// * `config` - Memory budget and rho settings.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn discrete_log(
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	discrete_log_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`discrete_log`] but also returns the statistics of the chosen solver.
+pub fn discrete_log_report(
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	let (bsgs, rho) = config.solvers();
+	if config.bsgs.fits(n) {
+		return bsgs_report(&bsgs, base, y, p, n)
+	}
+	try_pollard_rho_report(&rho, config.restarts, seed, base, y, p, n)
+}
+
+/// Same as [`discrete_log`] in any [`Group`] of order `n`.
+pub fn discrete_log_in<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	discrete_log_report_in(group, config, seed, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`discrete_log_in`] but also returns the statistics of the chosen solver.
+pub fn discrete_log_report_in<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)> {
+	let (bsgs, rho) = config.solvers();
+	if config.bsgs.fits(group.order()) {
+		bsgs_report_in(group, &bsgs, base, y)
+	} else {
+		try_pollard_rho_report_in(group, &rho, config.restarts, seed, base, y)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -188,7 +862,7 @@ mod tests {
 		let four = Integer::from(4);
 		let three = Integer::from(3);
 		assert_eq!(
-			num.div_rem_euc(four).1,
+			num.div_rem_euclid(&four).1,
 			three,
 			"The remainder of euclidean division does not match!"
 		);
@@ -202,12 +876,121 @@ mod tests {
 		for i in 0..100 {
 			// let num = gen_bigint_range(&mut rand, &two, &n);
 			let num = Integer::from(57);
-			let res = two.pow_mod_ref(&num, &p).unwrap();
-			let y = Integer::from(res);
+			let y = two.modpow(&num, &p);
+			let big_i = Integer::from(i);
+			let key = try_pollard_rho(10, &big_i, &two, &y, &p, &n).unwrap();
+			let res_key = num.rem_euclid(&n);
+			assert_eq!(&res_key, &key, "The found key {} is not the original key {}", key, num);
+		}
+	}
+
+	#[test]
+	fn test_pollard_rho_brent() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		for i in 0..100 {
+			let num = Integer::from(i * 7 + 3);
+			let y = two.modpow(&num, &p);
 			let big_i = Integer::from(i);
-			let key = try_pollard_rho(10, &big_i, &two, &y, &p, &n);
-			let res_key = Integer::from(&num.div_rem_euc_ref(&n).complete().1);
+			let config = RhoConfig { cycle: CycleDetection::Brent, ..Default::default() };
+			let key = try_pollard_rho_with(&config, 10, &big_i, &two, &y, &p, &n).unwrap();
+			let res_key = num.rem_euclid(&n);
 			assert_eq!(&res_key, &key, "The found key {} is not the original key {}", key, num);
 		}
 	}
+
+	#[test]
+	fn test_eqs_solutions() {
+		let n = Integer::from(12);
+		let (a1, b1, a2, b2) =
+			(Integer::from(3), Integer::from(5), Integer::from(11), Integer::from(1));
+		// 4*x = 8 (mod 12)
+		let solutions: Vec<Integer> = eqs_solutions(&a1, &b1, &a2, &b2, &n).collect();
+		assert_eq!(solutions, [2, 5, 8, 11].map(Integer::from));
+		assert_eq!(eqs_solvers(&a1, &b1, &a2, &b2, &n), Ok(Integer::from(2)));
+		// 4*x = 7 (mod 12) has no solution.
+		assert_eq!(eqs_solutions(&a1, &b1, &Integer::from(10), &b2, &n).count(), 0);
+		assert_eq!(
+			eqs_solvers(&a1, &b1, &Integer::from(10), &b2, &n),
+			Err(RhoError::NonInvertibleCollision)
+		);
+		// degenerate collision.
+		assert_eq!(eqs_solutions(&a1, &b1, &a2, &b1, &n).count(), 0);
+		assert_eq!(eqs_solvers(&a1, &b1, &a2, &b1, &n), Err(RhoError::DegenerateCollision));
+	}
+
+	#[test]
+	fn test_pollard_rho_composite_order() {
+		// 5 generates the whole group Z_383^* of order 382 = 2 * 191.
+		let p = Integer::from(383);
+		let n = Integer::from(382);
+		let five = Integer::from(5);
+		for i in 0..100 {
+			let num = Integer::from(i * 13 % 382);
+			let y = five.modpow(&num, &p);
+			let key = try_pollard_rho(10, &Integer::from(i), &five, &y, &p, &n).unwrap();
+			assert_eq!(key, num, "The found key {} is not the original key {}", key, num);
+		}
+	}
+
+	#[test]
+	fn test_invalid_order() {
+		let (p, two) = (Integer::from(383), Integer::from(2));
+		let res = try_pollard_rho(10, &BIG_INT_0, &two, &two, &p, &BIG_INT_0);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+
+	#[test]
+	fn test_discrete_log() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		let y = two.modpow(&Integer::from(57), &p);
+		// The balanced table of 14 entries either fits or the rho fallback is taken.
+		for max_table in [14, 13] {
+			let bsgs = BsgsConfig { max_table, ..Default::default() };
+			let config = SolverConfig { bsgs, ..Default::default() };
+			assert_eq!(discrete_log(&config, &BIG_INT_0, &two, &y, &p, &n), Ok(Integer::from(57)));
+			let (key, report) =
+				discrete_log_report(&config, &BIG_INT_0, &two, &y, &p, &n).unwrap();
+			assert_eq!(key, 57);
+			// BSGS finds no collision.
+			assert_eq!(report.collision.is_some(), max_table == 13);
+		}
+	}
+
+	#[test]
+	fn test_solve_report() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		let group = MultiplicativeGroup::new(p.clone(), n.clone());
+		let y = two.modpow(&Integer::from(57), &p);
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let config = RhoConfig { cycle, measure_cycle: true, ..Default::default() };
+			let (key, report) = try_pollard_rho_report(&config, 10, &BIG_INT_0, &two, &y, &p, &n)
+				.expect("Pollard rho should find the key!");
+			assert_eq!(key, 57);
+			// replay the successful walk and record the first visit of every point.
+			let mut rand = seeded_rand(&Integer::from(report.restarts));
+			let a = gen_bigint_range(&mut rand, &BIG_INT_0, &n);
+			let b = gen_bigint_range(&mut rand, &BIG_INT_0, &n);
+			let walker = Walker::new(config.walk, false, &mut rand, &group, &two, &y).unwrap();
+			let mut x = (walker.start(&a, &b), a, b);
+			let mut visited = std::collections::HashMap::new();
+			let mut i = 0u64;
+			while !visited.contains_key(&x.0) {
+				visited.insert(x.0.clone(), i);
+				x = walker.step(&x.0, &x.1, &x.2);
+				i += 1;
+			}
+			let tail = visited[&x.0];
+			assert_eq!(report.tail, Some(tail));
+			assert_eq!(report.cycle, Some(i - tail));
+			let (first, second) = report.collision.unwrap();
+			assert!(first >= tail && (second - first) % (i - tail) == 0);
+			assert!(report.group_operations >= report.iterations);
+		}
+	}
 }
@@ -0,0 +1,408 @@
+// Command-line front end of the DLP solvers.
+#![cfg_attr(not(feature = "rug"), allow(clippy::useless_conversion))]
+use pollard_rho::bsgs::{bsgs_report, BsgsConfig};
+use pollard_rho::budget::Budget;
+use pollard_rho::factor::{factorize_with, FactorConfig};
+use pollard_rho::generic::{RhoError, RhoResult, SolveReport};
+use pollard_rho::index_calculus::{index_calculus_report, IndexCalculusConfig};
+use pollard_rho::integer::{BigInteger, Integer};
+use pollard_rho::parallel::{parallel_rho_report, ParallelConfig};
+use pollard_rho::pohlig_hellman::pohlig_hellman_report;
+use pollard_rho::validate::order_of;
+use pollard_rho::{
+	discrete_log_report, try_pollard_rho_report, CycleDetection, RhoConfig, SolverConfig,
+};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Solves the DLP base^x = target (mod modulus).
+
+Usage: pollard_rho --base <int> --target <int> --modulus <int> [options]
+
+Options:
+    --order <int>          order of base, computed from the factorization of modulus - 1 if omitted
+    --seed <int>           seed of the pseudorandom walks [default: 0]
+    --max-restarts <n>     restarts of the randomized solvers with the next seed [default: 10]
+    --algorithm <name>     auto, rho, brent, parallel, bsgs, pohlig-hellman or index-calculus
+                           [default: auto]
+    --timeout <seconds>    stop the solver after this much wall-clock time
+    --max-iterations <n>   stop the solver after this many iterations
+    --measure-cycle        measure the exact tail and cycle length of the rho walk
+    --json                 print the result as a JSON object
+    -h, --help             print this help
+
+Integers are decimal or hexadecimal with a 0x prefix.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Algorithm {
+	Auto,
+	Rho,
+	Brent,
+	Parallel,
+	Bsgs,
+	PohligHellman,
+	IndexCalculus,
+}
+
+impl Algorithm {
+	fn parse(name: &str) -> Result<Self, String> {
+		match name {
+			"auto" => Ok(Algorithm::Auto),
+			"rho" => Ok(Algorithm::Rho),
+			"brent" => Ok(Algorithm::Brent),
+			"parallel" => Ok(Algorithm::Parallel),
+			"bsgs" => Ok(Algorithm::Bsgs),
+			"pohlig-hellman" => Ok(Algorithm::PohligHellman),
+			"index-calculus" => Ok(Algorithm::IndexCalculus),
+			_ => Err(format!("unknown algorithm {}", name)),
+		}
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Algorithm::Auto => "auto",
+			Algorithm::Rho => "rho",
+			Algorithm::Brent => "brent",
+			Algorithm::Parallel => "parallel",
+			Algorithm::Bsgs => "bsgs",
+			Algorithm::PohligHellman => "pohlig-hellman",
+			Algorithm::IndexCalculus => "index-calculus",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Args {
+	base: Integer,
+	target: Integer,
+	modulus: Integer,
+	order: Option<Integer>,
+	seed: Integer,
+	max_restarts: usize,
+	algorithm: Algorithm,
+	timeout: Option<Duration>,
+	max_iterations: Option<u64>,
+	measure_cycle: bool,
+	json: bool,
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_integer(s: &str) -> Result<Integer, String> {
+	let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+		Some(hex) => (hex, 16),
+		None => (s, 10),
+	};
+	Integer::read_radix(digits, radix).ok_or_else(|| format!("invalid integer {}", s))
+}
+
+/// Parses the command line, `None` when the help was requested.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+	let (mut base, mut target, mut modulus, mut order) = (None, None, None, None);
+	let mut seed = Integer::new();
+	let mut max_restarts = 10;
+	let mut algorithm = Algorithm::Auto;
+	let mut timeout = None;
+	let mut max_iterations = None;
+	let mut measure_cycle = false;
+	let mut json = false;
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		let (flag, inline) = match arg.split_once('=') {
+			Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+			None => (arg, None),
+		};
+		match flag.as_str() {
+			"-h" | "--help" => return Ok(None),
+			"--json" => {
+				json = true;
+				continue
+			},
+			"--measure-cycle" => {
+				measure_cycle = true;
+				continue
+			},
+			_ => {},
+		}
+		let value =
+			inline.or_else(|| args.next()).ok_or_else(|| format!("missing value for {}", flag))?;
+		match flag.as_str() {
+			"--base" => base = Some(parse_integer(&value)?),
+			"--target" => target = Some(parse_integer(&value)?),
+			"--modulus" => modulus = Some(parse_integer(&value)?),
+			"--order" => order = Some(parse_integer(&value)?),
+			"--seed" => seed = parse_integer(&value)?,
+			"--max-restarts" =>
+				max_restarts =
+					value.parse().map_err(|_| format!("invalid restart count {}", value))?,
+			"--algorithm" => algorithm = Algorithm::parse(&value)?,
+			"--timeout" => {
+				let seconds = value
+					.parse::<f64>()
+					.ok()
+					.filter(|s| s.is_finite() && *s >= 0.0)
+					.ok_or_else(|| format!("invalid timeout {}", value))?;
+				timeout = Some(Duration::from_secs_f64(seconds));
+			},
+			"--max-iterations" =>
+				max_iterations =
+					Some(value.parse().map_err(|_| format!("invalid iteration count {}", value))?),
+			_ => return Err(format!("unknown option {}", flag)),
+		}
+	}
+	Ok(Some(Args {
+		base: base.ok_or("missing --base")?,
+		target: target.ok_or("missing --target")?,
+		modulus: modulus.ok_or("missing --modulus")?,
+		order,
+		seed,
+		max_restarts,
+		algorithm,
+		timeout,
+		max_iterations,
+		measure_cycle,
+		json,
+	}))
+}
+
+/// Result of a run of the command.
+struct Outcome {
+	exponent: Integer,
+	order: Integer,
+	attempts: usize,
+	elapsed: Duration,
+	/// Statistics of the solver, the collision and cycle are only known to the rho walks.
+	report: SolveReport,
+}
+
+/// Runs `solve` with `seed`, `seed + 1`, ... until it succeeds or the restarts are used up.
+/// The report of the successful run counts the restarts before it.
+fn with_restarts(
+	args: &Args,
+	solve: impl Fn(&Integer) -> RhoResult<(Integer, SolveReport)>,
+) -> RhoResult<(Integer, SolveReport)> {
+	let mut seed = args.seed.clone();
+	let mut restarts = 0;
+	loop {
+		match solve(&seed) {
+			Ok((key, report)) => return Ok((key, SolveReport { restarts, ..report })),
+			Err(
+				RhoError::IterationLimit
+				| RhoError::DegenerateCollision
+				| RhoError::NonInvertibleCollision,
+			) if restarts < args.max_restarts => {
+				seed += 1;
+				restarts += 1;
+			},
+			Err(err) => return Err(err),
+		}
+	}
+}
+
+impl Outcome {
+	/// Lines of the plain text output.
+	fn to_text(&self, algorithm: &str) -> String {
+		let mut lines = vec![
+			format!("x = {}", self.exponent),
+			format!("order: {}", self.order),
+			format!("algorithm: {}", algorithm),
+			format!("attempts: {}", self.attempts),
+			format!("elapsed: {:.3} ms", self.elapsed.as_secs_f64() * 1000.0),
+			format!("iterations: {}", self.report.iterations),
+			format!("group operations: {}", self.report.group_operations),
+		];
+		let report = &self.report;
+		if let Some((i, j)) = report.collision {
+			lines.push(format!("collision: x_{} == x_{}", i, j));
+		}
+		if let Some(tail) = report.tail {
+			lines.push(format!("tail length: {}", tail));
+		}
+		if let Some(cycle) = report.cycle {
+			lines.push(format!("cycle length: {}", cycle));
+		}
+		lines.join("\n")
+	}
+
+	/// Big integers are written as strings so that no JSON parser loses precision.
+	fn to_json(&self, algorithm: &str) -> String {
+		let optional = |v: Option<u64>| v.map_or("null".to_string(), |v| v.to_string());
+		let report = &self.report;
+		let collision =
+			report.collision.map_or("null".to_string(), |(i, j)| format!("[{},{}]", i, j));
+		let fields = [
+			format!("\"exponent\":\"{}\"", self.exponent),
+			format!("\"order\":\"{}\"", self.order),
+			format!("\"algorithm\":\"{}\"", algorithm),
+			format!("\"attempts\":{}", self.attempts),
+			format!("\"elapsed_ms\":{:.3}", self.elapsed.as_secs_f64() * 1000.0),
+			format!("\"iterations\":{}", report.iterations),
+			format!("\"group_operations\":{}", report.group_operations),
+			format!("\"collision\":{}", collision),
+			format!("\"tail\":{}", optional(report.tail)),
+			format!("\"cycle\":{}", optional(report.cycle)),
+		];
+		format!("{{{}}}", fields.join(","))
+	}
+}
+
+fn run(args: &Args) -> RhoResult<Outcome> {
+	let start = Instant::now();
+	let Args { base, modulus: p, .. } = args;
+	let budget = Budget {
+		deadline: args.timeout.map(|timeout| start + timeout),
+		max_iterations: args.max_iterations,
+		..Default::default()
+	};
+	let order = match &args.order {
+		Some(order) => order.clone(),
+		None => {
+			let factor_config = FactorConfig { budget: budget.clone(), ..Default::default() };
+			let factors: Vec<(Integer, u32)> =
+				factorize_with(&Integer::from(p - 1), &factor_config)?.into_iter().collect();
+			order_of(base, p, &factors)?
+		},
+	};
+	let n = &order;
+	let (exponent, report) = solve(args, n, &budget)?;
+	let attempts = report.restarts + 1;
+	Ok(Outcome { exponent, order, attempts, elapsed: start.elapsed(), report })
+}
+
+/// Runs the chosen solver, its report counts the restarts with a new seed.
+fn solve(args: &Args, n: &Integer, budget: &Budget) -> RhoResult<(Integer, SolveReport)> {
+	let Args { base, target: y, modulus: p, .. } = args;
+	let budget = budget.clone();
+	let solver = SolverConfig { restarts: args.max_restarts, budget, ..Default::default() };
+	let budget = solver.budget.clone();
+	let rho = |cycle| {
+		let (measure_cycle, budget) = (args.measure_cycle, budget.clone());
+		let config = RhoConfig { cycle, measure_cycle, budget, ..Default::default() };
+		try_pollard_rho_report(&config, args.max_restarts, &args.seed, base, y, p, n)
+	};
+	match args.algorithm {
+		Algorithm::Auto => discrete_log_report(&solver, &args.seed, base, y, p, n),
+		Algorithm::Rho => rho(CycleDetection::Floyd),
+		Algorithm::Brent => rho(CycleDetection::Brent),
+		Algorithm::Parallel => with_restarts(args, |seed| {
+			let config = ParallelConfig { budget: budget.clone(), ..Default::default() };
+			parallel_rho_report(&config, seed, base, y, p, n)
+		}),
+		Algorithm::Bsgs => bsgs_report(&BsgsConfig { budget, ..Default::default() }, base, y, p, n),
+		Algorithm::PohligHellman => pohlig_hellman_report(&solver, &args.seed, base, y, p, n),
+		Algorithm::IndexCalculus => with_restarts(args, |seed| {
+			let config = IndexCalculusConfig { budget: budget.clone(), ..Default::default() };
+			index_calculus_report(&config, seed, base, y, p, n)
+		}),
+	}
+}
+
+fn main() -> ExitCode {
+	let args = match parse_args(std::env::args().skip(1)) {
+		Ok(Some(args)) => args,
+		Ok(None) => {
+			println!("{}", USAGE);
+			return ExitCode::SUCCESS
+		},
+		Err(err) => {
+			eprintln!("error: {}\n\n{}", err, USAGE);
+			return ExitCode::from(2)
+		},
+	};
+	let algorithm = args.algorithm.name();
+	match run(&args) {
+		Ok(outcome) if args.json => println!("{}", outcome.to_json(algorithm)),
+		Ok(outcome) => println!("{}", outcome.to_text(algorithm)),
+		Err(err) => {
+			if args.json {
+				let iterations = match &err {
+					RhoError::Cancelled(_, report) => report.iterations.to_string(),
+					_ => "null".to_string(),
+				};
+				let err = json_escape(&err.to_string());
+				println!(
+					"{{\"error\":\"{}\",\"algorithm\":\"{}\",\"iterations\":{}}}",
+					err, algorithm, iterations
+				);
+			} else {
+				eprintln!("error: {}", err);
+			}
+			return ExitCode::FAILURE
+		},
+	}
+	ExitCode::SUCCESS
+}
+
+fn json_escape(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pollard_rho::budget::StopReason;
+
+	fn args(line: &str) -> Result<Option<Args>, String> {
+		parse_args(line.split_whitespace().map(String::from))
+	}
+
+	#[test]
+	fn test_parse_args() {
+		let parsed = args("--base 2 --target=0x1f --modulus 0X17F --algorithm brent --json")
+			.unwrap()
+			.unwrap();
+		assert_eq!(parsed.base, 2);
+		assert_eq!(parsed.target, 31);
+		assert_eq!(parsed.modulus, 383);
+		assert_eq!(parsed.order, None);
+		assert_eq!(parsed.algorithm, Algorithm::Brent);
+		assert!(parsed.json);
+		assert_eq!(args("--help").unwrap(), None);
+		assert!(args("--base 2 --target 3").is_err());
+		assert!(args("--base 2 --target 3 --modulus 383 --seed").is_err());
+		assert!(args("--base 0xg --target 3 --modulus 383").is_err());
+		assert!(args("--base 2 --target 3 --modulus 383 --algorithm fast").is_err());
+		assert!(args("--base 2 --target 3 --modulus 383 --timeout -1").is_err());
+		let parsed = args("--base 2 --target 3 --modulus 383 --timeout 1.5 --max-iterations 10")
+			.unwrap()
+			.unwrap();
+		assert_eq!(parsed.timeout, Some(Duration::from_millis(1500)));
+		assert_eq!(parsed.max_iterations, Some(10));
+	}
+
+	#[test]
+	fn test_run() {
+		let y = Integer::from(2).modpow(&Integer::from(57), &Integer::from(383));
+		for algorithm in ["auto", "rho", "brent", "bsgs", "pohlig-hellman", "index-calculus"] {
+			let line = format!("--base 2 --target {} --modulus 383 --algorithm {}", y, algorithm);
+			let outcome = run(&args(&line).unwrap().unwrap()).unwrap();
+			assert_eq!(outcome.exponent, 57, "{}", algorithm);
+			assert_eq!(outcome.order, 191);
+			assert!(outcome.report.iterations > 0, "{}", algorithm);
+			assert_eq!(outcome.attempts, outcome.report.restarts + 1);
+			let json = outcome.to_json(algorithm);
+			assert!(json.contains("\"iterations\":") && json.contains("\"cycle\":"), "{}", json);
+			let rho = algorithm == "rho" || algorithm == "brent";
+			assert_eq!(outcome.report.collision.is_some(), rho);
+		}
+		let line =
+			format!("--base 2 --target {} --modulus 383 --algorithm brent --measure-cycle", y);
+		let report = run(&args(&line).unwrap().unwrap()).unwrap().report;
+		assert!(report.tail.is_some() && report.cycle.is_some());
+		// the distinguished points of the parallel walks are too sparse for a group of order 191.
+		let y = Integer::from(4).modpow(&Integer::from(123_456_789), &Integer::from(1_000_000_007));
+		let line = format!("--base 4 --target {} --modulus 1000000007 --algorithm parallel", y);
+		let outcome = run(&args(&line).unwrap().unwrap()).unwrap();
+		assert_eq!(outcome.exponent, 123_456_789);
+		assert!(outcome.report.iterations > 0);
+		// p - 1 = 2 * 4611686018427388039 * 4611686018427392159, finding the order of 2 would
+		// take billions of rho steps without the budget of the solver.
+		let p = "42535295865117348423525067721437972403";
+		let line = format!("--base 2 --target 3 --modulus {} --max-iterations 1000", p);
+		let res = run(&args(&line).unwrap().unwrap()).map(|outcome| outcome.exponent);
+		match res {
+			Err(RhoError::Cancelled(StopReason::IterationBudget, _)) => (),
+			res => panic!("unexpected result {:?}", res),
+		}
+	}
+}
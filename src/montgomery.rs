@@ -0,0 +1,623 @@
+// Montgomery representation of residues modulo a fixed odd modulus.
+// Source: P. L. Montgomery, "Modular Multiplication Without Trial Division",
+//         Mathematics of Computation 44 (1985), 519-521.
+//         C. K. Koc, T. Acar and B. S. Kaliski, "Analyzing and Comparing Montgomery
+//         Multiplication Algorithms", IEEE Micro 16 (1996), 26-33, for the CIOS method.
+use crate::budget::Budget;
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::integer::{seeded_rand, BigInteger, Integer};
+use crate::observer::NoObserver;
+use crate::utils::gen_bigint_range;
+use crate::validate::validate;
+use crate::walk::{Walk, Walker};
+use crate::{check_order, try_rho, CycleDetection, RhoConfig};
+use std::time::Instant;
+
+/// Largest supported modulus in 64-bit limbs, i.e. 4096 bits.
+pub const MAX_LIMBS: usize = 64;
+
+/// Residue `x * R mod p` with `R = 2^(64 * k)` for the `k` limbs of the modulus `p` of a
+/// [`Context`] of `N >= k` limbs. The limbs are stored inline, so residues never allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Residue<const N: usize> {
+	/// Little-endian limbs, those beyond the length of the modulus are zero.
+	limbs: [u64; N],
+}
+
+impl<const N: usize> Residue<N> {
+	const ZERO: Residue<N> = Residue { limbs: [0; N] };
+}
+
+/// Arithmetic modulo a fixed odd modulus `p` of at most `N` limbs in Montgomery form,
+/// where a product costs a multiplication and a reduction by shifts instead of a division by `p`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context<const N: usize> {
+	p: Integer,
+	/// The limbs of `p`.
+	modulus: Residue<N>,
+	/// Number of limbs of `p`.
+	len: usize,
+	/// `-p^-1 mod 2^64`.
+	inv: u64,
+	/// The limbs of `R^2 mod p`.
+	r2: Residue<N>,
+	/// Residue of 1, i.e. `R mod p`.
+	one: Residue<N>,
+}
+
+impl<const N: usize> Context<N> {
+	/// Context for the odd modulus `p > 1`, `None` for an even modulus or one of more
+	/// than `N` limbs.
+	pub fn new(p: &Integer) -> Option<Self> {
+		let bits = p.significant_bits() as usize;
+		if *p < 3 || p.is_even() || bits > 64 * N {
+			return None
+		}
+		let len = bits.div_ceil(64);
+		let modulus = limbs(p);
+		// Newton iteration for p^-1 mod 2^64, every step doubles the number of correct bits.
+		let mut inv = 1u64;
+		for _ in 0..6 {
+			inv = inv.wrapping_mul(2u64.wrapping_sub(modulus.limbs[0].wrapping_mul(inv)));
+		}
+		let r = Integer::from(1) << (64 * len) as u32;
+		let one = r.rem_euclid(p);
+		let r2 = Integer::from(&one * &one).rem_euclid(p);
+		Some(Context {
+			p: p.clone(),
+			modulus,
+			len,
+			inv: inv.wrapping_neg(),
+			r2: limbs(&r2),
+			one: limbs(&one),
+		})
+	}
+
+	/// The modulus `p`.
+	pub fn modulus(&self) -> &Integer {
+		&self.p
+	}
+
+	/// Residue of 1.
+	pub fn one(&self) -> Residue<N> {
+		self.one
+	}
+
+	/// Residue of `x`, which may be any integer.
+	pub fn to_residue(&self, x: &Integer) -> Residue<N> {
+		let x = x.rem_euclid(&self.p);
+		self.mul(&limbs(&x), &self.r2)
+	}
+
+	/// The integer in [0, p) represented by `a`.
+	pub fn to_integer(&self, a: &Residue<N>) -> Integer {
+		let plain = self.reduce(a);
+		plain.limbs[..self.len].iter().rev().fold(Integer::new(), |acc, &limb| (acc << 64u32) + limb)
+	}
+
+	/// Residue of the product, `a * b * R^-1 mod p` by coarsely integrated operand scanning.
+	pub fn mul(&self, a: &Residue<N>, b: &Residue<N>) -> Residue<N> {
+		let k = self.len;
+		let p = &self.modulus.limbs;
+		// the temporary has k + 2 limbs, the two above the modulus are kept apart.
+		let mut t = [0u64; N];
+		let mut t_k = 0u64;
+		for &b_i in &b.limbs[..k] {
+			// t += a * b_i
+			let mut carry = 0u64;
+			for (t_j, &a_j) in t.iter_mut().zip(&a.limbs[..k]) {
+				let s = *t_j as u128 + a_j as u128 * b_i as u128 + carry as u128;
+				*t_j = s as u64;
+				carry = (s >> 64) as u64;
+			}
+			let s = t_k as u128 + carry as u128;
+			t_k = s as u64;
+			let t_k1 = (s >> 64) as u64;
+			// t = (t + m * p) / 2^64, where m makes the lowest limb vanish.
+			let m = t[0].wrapping_mul(self.inv);
+			let s = t[0] as u128 + m as u128 * p[0] as u128;
+			let mut carry = (s >> 64) as u64;
+			for j in 1..k {
+				let s = t[j] as u128 + m as u128 * p[j] as u128 + carry as u128;
+				t[j - 1] = s as u64;
+				carry = (s >> 64) as u64;
+			}
+			let s = t_k as u128 + carry as u128;
+			t[k - 1] = s as u64;
+			t_k = t_k1 + (s >> 64) as u64;
+		}
+		// the result is less than 2p, a single subtraction brings it into [0, p).
+		let mut res = Residue { limbs: t };
+		if t_k != 0 || !self.below_modulus(&res) {
+			let mut borrow = false;
+			for (r, &p_j) in res.limbs[..k].iter_mut().zip(p) {
+				let (d, b1) = r.overflowing_sub(p_j);
+				let (d, b2) = d.overflowing_sub(borrow as u64);
+				*r = d;
+				borrow = b1 || b2;
+			}
+		}
+		res
+	}
+
+	/// Residue of `a^k` for a non-negative exponent `k`.
+	pub fn pow(&self, a: &Residue<N>, k: &Integer) -> Residue<N> {
+		let mut res = self.one;
+		for i in (0..k.significant_bits()).rev() {
+			res = self.mul(&res, &res);
+			if k.get_bit(i) {
+				res = self.mul(&res, a);
+			}
+		}
+		res
+	}
+
+	/// Partition in [0, m) of the residue `a`, taken from the lowest limb of its Montgomery
+	/// form `x * R mod p` rather than from `x`, which would cost a reduction.
+	pub fn partition(&self, a: &Residue<N>, m: u32) -> u32 {
+		(a.limbs[0] % m as u64) as u32
+	}
+
+	/// The limbs of the integer represented by `a`, i.e. `a * R^-1 mod p`.
+	fn reduce(&self, a: &Residue<N>) -> Residue<N> {
+		let mut one = Residue::ZERO;
+		one.limbs[0] = 1;
+		self.mul(a, &one)
+	}
+
+	fn below_modulus(&self, a: &Residue<N>) -> bool {
+		let k = self.len;
+		a.limbs[..k].iter().rev().lt(self.modulus.limbs[..k].iter().rev())
+	}
+}
+
+/// Little-endian limbs of a non-negative integer of at most `N` limbs.
+fn limbs<const N: usize>(x: &Integer) -> Residue<N> {
+	let mut res = Residue::ZERO;
+	let words = (x.significant_bits() as usize).div_ceil(64);
+	for (i, limb) in res.limbs.iter_mut().enumerate().take(words) {
+		*limb = Integer::from(x >> (64 * i) as u32).to_u64_wrapping();
+	}
+	res
+}
+
+/// A [`Context`] whose residues have as many limbs as the modulus, rounded up to a power
+/// of two, so small moduli neither copy nor multiply the unused limbs of a wide one.
+/// The contexts are boxed, the widest ones take a few kilobytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Montgomery {
+	/// Moduli of a single limb.
+	Limbs1(Box<Context<1>>),
+	/// Moduli of 2 limbs.
+	Limbs2(Box<Context<2>>),
+	/// Moduli of 3 or 4 limbs.
+	Limbs4(Box<Context<4>>),
+	/// Moduli of 5 to 8 limbs.
+	Limbs8(Box<Context<8>>),
+	/// Moduli of 9 to 16 limbs.
+	Limbs16(Box<Context<16>>),
+	/// Moduli of 17 to 32 limbs.
+	Limbs32(Box<Context<32>>),
+	/// Moduli of 33 to [`MAX_LIMBS`] limbs.
+	Limbs64(Box<Context<MAX_LIMBS>>),
+}
+
+/// Evaluates `$body` with `$ctx` bound to the [`Context`] of any width.
+macro_rules! with_context {
+	($montgomery:expr, $ctx:ident => $body:expr) => {
+		match $montgomery {
+			Montgomery::Limbs1($ctx) => $body,
+			Montgomery::Limbs2($ctx) => $body,
+			Montgomery::Limbs4($ctx) => $body,
+			Montgomery::Limbs8($ctx) => $body,
+			Montgomery::Limbs16($ctx) => $body,
+			Montgomery::Limbs32($ctx) => $body,
+			Montgomery::Limbs64($ctx) => $body,
+		}
+	};
+}
+
+impl Montgomery {
+	/// Context for the odd modulus `p > 1` with the fewest limbs, `None` for an even modulus
+	/// or one of more than [`MAX_LIMBS`] limbs.
+	pub fn new(p: &Integer) -> Option<Self> {
+		let montgomery = match (p.significant_bits() as usize).div_ceil(64) {
+			0..=1 => Montgomery::Limbs1(Box::new(Context::new(p)?)),
+			2 => Montgomery::Limbs2(Box::new(Context::new(p)?)),
+			3..=4 => Montgomery::Limbs4(Box::new(Context::new(p)?)),
+			5..=8 => Montgomery::Limbs8(Box::new(Context::new(p)?)),
+			9..=16 => Montgomery::Limbs16(Box::new(Context::new(p)?)),
+			17..=32 => Montgomery::Limbs32(Box::new(Context::new(p)?)),
+			_ => Montgomery::Limbs64(Box::new(Context::new(p)?)),
+		};
+		Some(montgomery)
+	}
+
+	/// The modulus `p`.
+	pub fn modulus(&self) -> &Integer {
+		with_context!(self, ctx => ctx.modulus())
+	}
+
+	/// Same as [`crate::pollard_rho_report`] for `base` of order `n` modulo this modulus.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		seed: &Integer,
+		base: &Integer,
+		y: &Integer,
+		n: &Integer,
+	) -> RhoResult<(Integer, SolveReport)> {
+		with_context!(self, ctx => ctx.pollard_rho_report(config, seed, base, y, n))
+	}
+
+	/// Same as [`crate::try_pollard_rho_report`] for `base` of order `n` modulo this modulus.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn try_pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		limit: usize,
+		seed: &Integer,
+		base: &Integer,
+		y: &Integer,
+		n: &Integer,
+	) -> RhoResult<(Integer, SolveReport)> {
+		with_context!(self, ctx => ctx.try_pollard_rho_report(config, limit, seed, base, y, n))
+	}
+}
+
+/// The walk of a [`Walker`] on residues, with the exponents updated in place.
+struct MontgomeryWalk<'a, const N: usize> {
+	ctx: &'a Context<N>,
+	walk: Walk,
+	partitions: u32,
+	base: Residue<N>,
+	y: Residue<N>,
+	multipliers: Vec<(Residue<N>, Integer, Integer)>,
+	n: &'a Integer,
+}
+
+impl<'a, const N: usize> MontgomeryWalk<'a, N> {
+	fn new(
+		ctx: &'a Context<N>,
+		walker: &Walker<'a, MultiplicativeGroup>,
+		base: &Integer,
+		y: &Integer,
+	) -> Self {
+		let multipliers = walker
+			.multipliers()
+			.map(|(value, m, n)| (ctx.to_residue(value), m.clone(), n.clone()))
+			.collect();
+		MontgomeryWalk {
+			ctx,
+			walk: walker.walk(),
+			partitions: walker.partitions(),
+			base: ctx.to_residue(base),
+			y: ctx.to_residue(y),
+			multipliers,
+			n: walker.group().order(),
+		}
+	}
+
+	/// The step of [`Walker::step`] on the partitions of [`Context::partition`], none of the
+	/// updates allocates once the exponents have grown to their final size.
+	fn step(&self, x: &mut Residue<N>, a: &mut Integer, b: &mut Integer) {
+		let ctx = self.ctx;
+		let partition = ctx.partition(x, self.partitions);
+		let multiplier = match self.walk {
+			// the partitions of func_f, func_g and func_h.
+			Walk::Pollard => match partition {
+				0 => None,
+				1 => {
+					*x = ctx.mul(&self.base, x);
+					add_mod(a, 1u32, self.n);
+					return
+				},
+				_ => {
+					*x = ctx.mul(&self.y, x);
+					add_mod(b, 1u32, self.n);
+					return
+				},
+			},
+			_ => self.multipliers.get(partition as usize),
+		};
+		match multiplier {
+			Some((value, m, n)) => {
+				*x = ctx.mul(x, value);
+				add_mod(a, m, self.n);
+				add_mod(b, n, self.n);
+			},
+			None => {
+				*x = ctx.mul(x, x);
+				*a <<= 1;
+				reduce_once(a, self.n);
+				*b <<= 1;
+				reduce_once(b, self.n);
+			},
+		}
+	}
+}
+
+/// `v = (v + d) mod n` for `v` in [0, n) and `d` in [0, n].
+fn add_mod<T>(v: &mut Integer, d: T, n: &Integer)
+where
+	Integer: std::ops::AddAssign<T>,
+{
+	*v += d;
+	reduce_once(v, n);
+}
+
+fn reduce_once(v: &mut Integer, n: &Integer) {
+	if *v >= *n {
+		*v -= n;
+	}
+}
+
+/// Same as [`crate::pollard_rho_with`] but always runs the walk on [`Residue`]s, which avoids
+/// the division by `p` and the allocation of a new integer on every step.
+/// [`crate::pollard_rho_with`] takes this walk itself for every odd `p` beyond a u64. The walk
+/// draws the same multipliers as the one on [`Integer`]s with the same seed, but partitions
+/// the points by their Montgomery form, so it takes another path to the same key.
+/// [`RhoConfig::measure_cycle`] is ignored.
+/// # Arguments
+/// * `config` - Cycle detection algorithm, iteration function and budget of the walk.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn pollard_rho_montgomery(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	pollard_rho_montgomery_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`pollard_rho_montgomery`] but also returns the statistics of the walk.
+pub fn pollard_rho_montgomery_report(
+	config: &RhoConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	let ctx = Montgomery::new(p).ok_or_else(|| {
+		let reason = if p.is_even() {
+			"is even".to_string()
+		} else if *p < 3 {
+			"is below 3".to_string()
+		} else {
+			format!("is wider than {} limbs", MAX_LIMBS)
+		};
+		RhoError::InvalidParameters(format!("modulus {} {}", p, reason))
+	})?;
+	ctx.pollard_rho_report(config, seed, base, y, n)
+}
+
+impl<const N: usize> Context<N> {
+	/// Same as [`crate::pollard_rho_report`] for `base` of order `n` modulo this modulus.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		seed: &Integer,
+		base: &Integer,
+		y: &Integer,
+		n: &Integer,
+	) -> RhoResult<(Integer, SolveReport)> {
+		let (res, report) = self.rho_attempt(config, seed, base, y, n);
+		res.map(|key| (key, report))
+	}
+
+	/// Same as [`crate::try_pollard_rho_report`] for `base` of order `n` modulo this modulus.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn try_pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		limit: usize,
+		seed: &Integer,
+		base: &Integer,
+		y: &Integer,
+		n: &Integer,
+	) -> RhoResult<(Integer, SolveReport)> {
+		try_rho(config, limit, seed, &mut NoObserver, |config, seed, _| {
+			self.rho_attempt(config, seed, base, y, n)
+		})
+	}
+
+	/// A single walk from the seed, the same as [`crate::rho_attempt`] without checkpoints.
+	fn rho_attempt(
+		&self,
+		config: &RhoConfig,
+		seed: &Integer,
+		base: &Integer,
+		y: &Integer,
+		n: &Integer,
+	) -> (RhoResult<Integer>, SolveReport) {
+		let start = Instant::now();
+		let mut report = SolveReport::default();
+		if let Err(err) = check_order(n) {
+			return (Err(err), report)
+		}
+		let group = MultiplicativeGroup::new(self.p.clone(), n.clone());
+		// draw the same random numbers as the walk on Integers.
+		let mut rand = seeded_rand(seed);
+		let a = gen_bigint_range(&mut rand, &Integer::ZERO, n);
+		let b = gen_bigint_range(&mut rand, &Integer::ZERO, n);
+		let (walk, negation_map) = (config.walk, config.negation_map);
+		let walker = match Walker::new(walk, negation_map, &mut rand, &group, base, y) {
+			Ok(walker) => walker,
+			Err(err) => return (Err(err), report),
+		};
+		let walk = MontgomeryWalk::new(self, &walker, base, y);
+		let x = self.mul(&self.pow(&walk.base, &a), &self.pow(&walk.y, &b));
+		let budget = &config.budget;
+		let mut res = match config.cycle {
+			CycleDetection::Floyd => floyd(&walk, &walker, (x, a, b), budget, &mut report),
+			CycleDetection::Brent => brent(&walk, &walker, (x, a, b), budget, &mut report),
+		};
+		report.elapsed = start.elapsed();
+		if let Err(RhoError::Cancelled(_, partial)) = &mut res {
+			**partial = report.clone();
+		}
+		(res, report)
+	}
+}
+
+fn floyd<const N: usize>(
+	walk: &MontgomeryWalk<N>,
+	walker: &Walker<MultiplicativeGroup>,
+	(x, a, b): (Residue<N>, Integer, Integer),
+	budget: &Budget,
+	report: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let (mut x_i, mut a_i, mut b_i) = (x, a.clone(), b.clone());
+	let (mut x_2i, mut a_2i, mut b_2i) = (x, a, b);
+	while *walk.n > report.iterations {
+		budget
+			.check(report.iterations)
+			.map_err(|reason| reason.into_error(SolveReport::default()))?;
+		walk.step(&mut x_i, &mut a_i, &mut b_i);
+		walk.step(&mut x_2i, &mut a_2i, &mut b_2i);
+		walk.step(&mut x_2i, &mut a_2i, &mut b_2i);
+		report.iterations += 1;
+		report.group_operations += 3;
+		if x_i == x_2i {
+			report.collision = Some((report.iterations, report.iterations * 2));
+			return walker.solve(&a_i, &b_i, &a_2i, &b_2i)
+		}
+	}
+	Err(RhoError::IterationLimit)
+}
+
+fn brent<const N: usize>(
+	walk: &MontgomeryWalk<N>,
+	walker: &Walker<MultiplicativeGroup>,
+	(x, a, b): (Residue<N>, Integer, Integer),
+	budget: &Budget,
+	report: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let (mut x_i, mut a_i, mut b_i) = (x, a.clone(), b.clone());
+	let (mut x_s, mut a_s, mut b_s) = (x, a, b);
+	let limit = Integer::from(walk.n * 3);
+	let (mut power, mut lam) = (1u64, 0u64);
+	while limit > report.iterations {
+		budget
+			.check(report.iterations)
+			.map_err(|reason| reason.into_error(SolveReport::default()))?;
+		walk.step(&mut x_i, &mut a_i, &mut b_i);
+		lam += 1;
+		report.iterations += 1;
+		report.group_operations += 1;
+		if x_i == x_s {
+			report.collision = Some((report.iterations - lam, report.iterations));
+			report.cycle = Some(lam);
+			return walker.solve(&a_s, &b_s, &a_i, &b_i)
+		}
+		if lam == power {
+			// only allocates once per power of two.
+			x_s = x_i;
+			a_s.clone_from(&a_i);
+			b_s.clone_from(&b_i);
+			power *= 2;
+			lam = 0;
+		}
+	}
+	Err(RhoError::IterationLimit)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::integer::RandState;
+	use crate::{pollard_rho_report, try_pollard_rho_report};
+
+	/// Residues of `N` limbs modulo `p` agree with the arithmetic on integers.
+	fn check_context<const N: usize>(p: &Integer, rand: &mut RandState) {
+		let ctx = Context::<N>::new(p).unwrap();
+		assert_eq!(ctx.to_integer(&ctx.one()), 1);
+		for _ in 0..50 {
+			let x = gen_bigint_range(rand, &Integer::ZERO, p);
+			let y = gen_bigint_range(rand, &Integer::ZERO, p);
+			let (rx, ry) = (ctx.to_residue(&x), ctx.to_residue(&y));
+			assert_eq!(ctx.to_integer(&rx), x);
+			let product = Integer::from(&x * &y).rem_euclid(p);
+			assert_eq!(ctx.to_integer(&ctx.mul(&rx, &ry)), product);
+			let montgomery_form = Integer::from(&x << (64 * ctx.len) as u32).rem_euclid(p);
+			assert_eq!(ctx.partition(&rx, 20) as u64, montgomery_form.to_u64_wrapping() % 20);
+			let power = x.modpow(&y, p);
+			assert_eq!(ctx.to_integer(&ctx.pow(&rx, &y)), power);
+		}
+	}
+
+	#[test]
+	fn test_montgomery() {
+		let mut rand = seeded_rand(&Integer::new());
+		for p in [Integer::from(383), Integer::from(1_000_000_007u64), Integer::from(u64::MAX - 58)]
+		{
+			check_context::<1>(&p, &mut rand);
+			assert!(matches!(Montgomery::new(&p), Some(Montgomery::Limbs1(_))));
+		}
+		let mersenne_127 = (Integer::from(1) << 127) - 1u32;
+		check_context::<2>(&mersenne_127, &mut rand);
+		// the limbs beyond the 9 of the modulus stay unused.
+		let mersenne_521 = (Integer::from(1) << 521) - 1u32;
+		check_context::<16>(&mersenne_521, &mut rand);
+		check_context::<MAX_LIMBS>(&mersenne_521, &mut rand);
+		assert!(matches!(Montgomery::new(&mersenne_521), Some(Montgomery::Limbs16(_))));
+		assert!(Context::<1>::new(&mersenne_127).is_none());
+		assert!(Montgomery::new(&Integer::from(1_000_000_008u64)).is_none());
+		let (one, two) = (Integer::from(1), Integer::from(2));
+		let config = RhoConfig::default();
+		let res = pollard_rho_montgomery(&config, &one, &one, &one, &two, &one);
+		assert_eq!(res, Err(RhoError::InvalidParameters("modulus 2 is even".into())));
+	}
+
+	#[test]
+	fn test_pollard_rho_montgomery() {
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let four = Integer::from(4);
+		let y = four.modpow(&Integer::from(123_456_789), &p);
+		let ctx = Montgomery::new(&p).unwrap();
+		for walk in [Walk::Pollard, Walk::Adding { r: 20 }, Walk::Mixed { r: 16, squarings: 4 }] {
+			for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+				let config = RhoConfig { cycle, walk, ..Default::default() };
+				let seed = Integer::from(1);
+				let (found, report) =
+					ctx.try_pollard_rho_report(&config, 10, &seed, &four, &y, &n).unwrap();
+				assert_eq!(found, 123_456_789);
+				assert!(report.collision.is_some());
+			}
+		}
+	}
+
+	#[test]
+	fn test_default_walk_on_residues() {
+		// p - 1 = 1844670902522 * n for the prime n, p does not fit into a u64.
+		let p = Integer::from(18_446_744_073_967_147_919u128);
+		let n = Integer::from(10_000_019);
+		let base = Integer::from(2).modpow(&Integer::from(1_844_670_902_522u64), &p);
+		let key = Integer::from(7_654_321);
+		let y = base.modpow(&key, &p);
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let config = RhoConfig { cycle, walk: Walk::Adding { r: 20 }, ..Default::default() };
+			let seed = Integer::from(3);
+			let (expected, expected_report) =
+				pollard_rho_montgomery_report(&config, &seed, &base, &y, &p, &n).unwrap();
+			let (found, report) = pollard_rho_report(&config, &seed, &base, &y, &p, &n).unwrap();
+			assert_eq!((found, expected), (key.clone(), key.clone()));
+			assert_eq!(report.iterations, expected_report.iterations);
+			assert_eq!(report.collision, expected_report.collision);
+			let (found, _) = try_pollard_rho_report(&config, 3, &seed, &base, &y, &p, &n).unwrap();
+			assert_eq!(found, key);
+		}
+	}
+}
@@ -0,0 +1,144 @@
+// Progress reporting of long running walks.
+use crate::integer::Integer;
+
+/// Default number of iterations between two [`Observer::on_progress`] calls.
+pub const DEFAULT_INTERVAL: u64 = 1 << 16;
+
+/// Snapshot of a running solver handed to an [`Observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress<'a> {
+	/// Iterations so far, summed over all restarts.
+	pub iterations: u64,
+	/// Seed of the walk that is currently running.
+	pub seed: &'a Integer,
+	/// Distinguished points stored so far, always zero for the sequential walks.
+	pub distinguished_points: u64,
+}
+
+/// Hook the rho solvers call while they run, e.g. to feed a progress bar or a dashboard.
+/// The solvers are generic over the observer, so [`NoObserver`] compiles down to the
+/// plain loop without any checks.
+pub trait Observer {
+	/// Whether the solvers call this observer at all.
+	const ENABLED: bool = true;
+
+	/// Number of iterations between two [`Observer::on_progress`] calls.
+	fn interval(&self) -> u64 {
+		DEFAULT_INTERVAL
+	}
+
+	/// Called every [`Observer::interval`] iterations.
+	fn on_progress(&mut self, _progress: &Progress) {}
+
+	/// Called when a failed walk is restarted, `progress.seed` is the seed of the new walk.
+	fn on_restart(&mut self, _progress: &Progress) {}
+}
+
+/// The observer that ignores everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {
+	const ENABLED: bool = false;
+}
+
+/// Calls `observer` if `iterations` is a multiple of its interval.
+#[inline(always)]
+pub(crate) fn notify<O: Observer>(
+	observer: &mut O,
+	iterations: u64,
+	seed: &Integer,
+	distinguished_points: u64,
+) {
+	if O::ENABLED && iterations.is_multiple_of(observer.interval().max(1)) {
+		observer.on_progress(&Progress { iterations, seed, distinguished_points });
+	}
+}
+
+/// Counts the iterations of the earlier attempts into the progress of a restarted walk.
+pub(crate) struct Offset<'a, O: Observer> {
+	pub observer: &'a mut O,
+	pub iterations: u64,
+}
+
+impl<'a, O: Observer> Observer for Offset<'a, O> {
+	const ENABLED: bool = O::ENABLED;
+
+	fn interval(&self) -> u64 {
+		self.observer.interval()
+	}
+
+	fn on_progress(&mut self, progress: &Progress) {
+		let iterations = self.iterations + progress.iterations;
+		self.observer.on_progress(&Progress { iterations, ..*progress })
+	}
+
+	fn on_restart(&mut self, progress: &Progress) {
+		let iterations = self.iterations + progress.iterations;
+		self.observer.on_restart(&Progress { iterations, ..*progress })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::integer::BigInteger;
+	use crate::parallel::{parallel_rho_observed, ParallelConfig};
+	use crate::{try_pollard_rho_observed, try_pollard_rho_report, CycleDetection, RhoConfig};
+
+	#[derive(Default)]
+	struct Recorder {
+		progress: Vec<(u64, u64)>,
+		restarts: Vec<(u64, Integer)>,
+	}
+
+	impl Observer for Recorder {
+		fn interval(&self) -> u64 {
+			4
+		}
+
+		fn on_progress(&mut self, progress: &Progress) {
+			self.progress.push((progress.iterations, progress.distinguished_points));
+		}
+
+		fn on_restart(&mut self, progress: &Progress) {
+			self.restarts.push((progress.iterations, progress.seed.clone()));
+		}
+	}
+
+	#[test]
+	fn test_observer() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		let y = two.modpow(&Integer::from(77), &p);
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let config = RhoConfig { cycle, ..Default::default() };
+			for i in 0..10 {
+				let seed = Integer::from(i);
+				let mut recorder = Recorder::default();
+				let key =
+					try_pollard_rho_observed(&config, 10, &seed, &two, &y, &p, &n, &mut recorder)
+						.unwrap();
+				let (_, report) =
+					try_pollard_rho_report(&config, 10, &seed, &two, &y, &p, &n).unwrap();
+				assert_eq!(key, 77);
+				assert_eq!(recorder.restarts.len(), report.restarts);
+				for (k, (_, restart_seed)) in recorder.restarts.iter().enumerate() {
+					assert_eq!(*restart_seed, Integer::from(&seed + (k + 1) as u32));
+				}
+				assert!(report.iterations < 4 || !recorder.progress.is_empty());
+				assert!(recorder.progress.windows(2).all(|w| w[0].0 < w[1].0));
+				assert!(recorder
+					.progress
+					.iter()
+					.all(|&(iterations, dps)| iterations <= report.iterations && dps == 0));
+			}
+		}
+		let config = ParallelConfig { threads: 2, distinguished_bits: 2, ..Default::default() };
+		let mut recorder = Recorder::default();
+		let key = parallel_rho_observed(&config, &Integer::new(), &two, &y, &p, &n, &mut recorder);
+		assert_eq!(key.unwrap(), 77);
+		assert!(recorder.progress.iter().all(|&(iterations, _)| iterations % 4 == 0));
+	}
+}
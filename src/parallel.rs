@@ -0,0 +1,317 @@
+// Parallel collision search with distinguished points.
+// Source: P. C. van Oorschot and M. J. Wiener, "Parallel Collision Search with Cryptanalytic
+//         Applications", Journal of Cryptology, 1999.
+use crate::integer::{seeded_rand, Integer};
+use crate::utils::gen_bigint_range;
+use crate::budget::{Budget, StopReason};
+use crate::check_order;
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup};
+use crate::observer::{NoObserver, Observer, Progress};
+use crate::validate::validate;
+use crate::walk::{Walk, Walker};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+/// Configuration of the distinguished-point parallel rho.
+#[derive(Debug, Clone)]
+pub struct ParallelConfig {
+	/// Number of worker threads, each thread runs its own sequence of walks.
+	pub threads: usize,
+	/// A point is distinguished if the hash of it has at least this many trailing zero bits.
+	pub distinguished_bits: u32,
+	/// Iteration function shared by all walks.
+	pub walk: Walk,
+	/// Walk on the classes {x, x^-1}, see [`crate::RhoConfig::negation_map`]. Walks trapped
+	/// in a fruitless cycle are abandoned once they exceed the maximum walk length.
+	pub negation_map: bool,
+	/// Cancellation token and limits, the iterations are the walk steps of all threads.
+	pub budget: Budget,
+}
+
+impl Default for ParallelConfig {
+	fn default() -> Self {
+		ParallelConfig {
+			threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+			distinguished_bits: 8,
+			walk: Walk::default(),
+			negation_map: false,
+			budget: Budget::default(),
+		}
+	}
+}
+
+impl ParallelConfig {
+	/// Walks which do not reach a distinguished point after this many steps are
+	/// most likely trapped in a cycle and get abandoned.
+	fn max_walk_length(&self) -> u64 {
+		20u64 << self.distinguished_bits.min(40)
+	}
+}
+
+/// Check whether the point encoded as `x` meets the distinguished point criterion.
+pub fn is_distinguished(x: &Integer, bits: u32) -> bool {
+	let mut hasher = DefaultHasher::new();
+	x.hash(&mut hasher);
+	hasher.finish().trailing_zeros() >= bits
+}
+
+/// Computes `x` = a mod n for the DLP base**x mod p == y with van Oorschot-Wiener
+/// parallel collision search.
+/// Every thread starts walks from random (a, b) pairs and only reports the distinguished
+/// points to a shared table, a collision between any two walks is solved by [`crate::eqs_solvers_in`].
+/// # Arguments
+/// * `config` - Number of threads and the distinguished point criterion.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`. Should be prime for this implementation.
+pub fn parallel_rho(
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	parallel_rho_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`parallel_rho`] but also returns the walk steps of all threads.
+pub fn parallel_rho_report(
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	parallel_rho_report_in(&group, config, seed, base, y)
+}
+
+/// Same as [`parallel_rho`] in any [`Group`] whose elements can be shared between threads.
+pub fn parallel_rho_in<G>(
+	group: &G,
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer>
+where
+	G: Group + Sync,
+	G::Element: Send + Sync,
+{
+	parallel_rho_report_in(group, config, seed, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`parallel_rho_in`] but also returns the walk steps of all threads.
+pub fn parallel_rho_report_in<G>(
+	group: &G,
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)>
+where
+	G: Group + Sync,
+	G::Element: Send + Sync,
+{
+	search(group, config, seed, base, y, &mut NoObserver)
+}
+
+/// Same as [`parallel_rho`] but calls `observer` while the threads run.
+/// The progress counts the walk steps of all threads and carries the seed of the
+/// thread that crossed the interval.
+pub fn parallel_rho_observed<O: Observer + Send>(
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+	observer: &mut O,
+) -> RhoResult<Integer> {
+	validate(base, y, p, n)?;
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	parallel_rho_observed_in(&group, config, seed, base, y, observer)
+}
+
+/// Same as [`parallel_rho_in`] but calls `observer` while the threads run.
+pub fn parallel_rho_observed_in<G, O>(
+	group: &G,
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	observer: &mut O,
+) -> RhoResult<Integer>
+where
+	G: Group + Sync,
+	G::Element: Send + Sync,
+	O: Observer + Send,
+{
+	search(group, config, seed, base, y, observer).map(|(key, _)| key)
+}
+
+/// Runs the threads until one of them solves a collision between two walks.
+fn search<G, O>(
+	group: &G,
+	config: &ParallelConfig,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	observer: &mut O,
+) -> RhoResult<(Integer, SolveReport)>
+where
+	G: Group + Sync,
+	G::Element: Send + Sync,
+	O: Observer + Send,
+{
+	check_order(group.order())?;
+	// all walks must share the same multipliers, otherwise colliding walks would not merge.
+	let mut rand = seeded_rand(seed);
+	let walker = Walker::new(config.walk, config.negation_map, &mut rand, group, base, y)?;
+	let start = Instant::now();
+	let table: Mutex<HashMap<Integer, (Integer, Integer)>> = Mutex::new(HashMap::new());
+	let found = AtomicBool::new(false);
+	let steps = AtomicU64::new(0);
+	let distinguished = AtomicU64::new(0);
+	let interval = observer.interval().max(1);
+	let observer = Mutex::new(observer);
+	let result: Mutex<Option<RhoResult<(Integer, SolveReport)>>> = Mutex::new(None);
+	thread::scope(|s| {
+		for t in 0..config.threads.max(1) {
+			let thread_seed = Integer::from(seed + (t as u32 + 1));
+			let shared = Shared {
+				table: &table,
+				found: &found,
+				steps: &steps,
+				distinguished: &distinguished,
+				observer: &observer,
+				interval,
+			};
+			let (walker, result) = (&walker, &result);
+			s.spawn(move || {
+				let Some(res) = worker(config, walker, &thread_seed, &shared).transpose() else {
+					return
+				};
+				let steps = shared.steps.load(Ordering::Relaxed);
+				let report = SolveReport {
+					iterations: steps,
+					group_operations: steps,
+					elapsed: start.elapsed(),
+					..Default::default()
+				};
+				let res = match res {
+					Ok(key) => Ok((key, report)),
+					Err(reason) => Err(reason.into_error(report)),
+				};
+				result.lock().unwrap().get_or_insert(res);
+				shared.found.store(true, Ordering::Relaxed);
+			});
+		}
+	});
+	result.into_inner().unwrap().unwrap_or(Err(RhoError::IterationLimit))
+}
+
+/// State shared by the worker threads.
+struct Shared<'a, O> {
+	table: &'a Mutex<HashMap<Integer, (Integer, Integer)>>,
+	/// Set once a thread found the key or stopped, the others quit after their current walk.
+	found: &'a AtomicBool,
+	/// Walk steps of all threads, checked against the budget.
+	steps: &'a AtomicU64,
+	/// Number of distinguished points in the table.
+	distinguished: &'a AtomicU64,
+	observer: &'a Mutex<&'a mut O>,
+	/// Walk steps between two progress reports.
+	interval: u64,
+}
+
+fn worker<G: Group, O: Observer>(
+	config: &ParallelConfig,
+	walker: &Walker<G>,
+	seed: &Integer,
+	shared: &Shared<O>,
+) -> Result<Option<Integer>, StopReason> {
+	let group = walker.group();
+	let n = group.order();
+	let mut rand = seeded_rand(seed);
+	let zero = Integer::new();
+	let max_walk_length = config.max_walk_length();
+	let mut steps = Integer::new();
+	while &steps < n {
+		// start a new walk from a random point base^a * y^b.
+		let a_i = gen_bigint_range(&mut rand, &zero, n);
+		let b_i = gen_bigint_range(&mut rand, &zero, n);
+		let (mut a_i, mut b_i) = walker.enter(a_i, b_i);
+		let mut x_i = walker.start(&a_i, &b_i);
+		let mut key = group.encode(&x_i);
+		let mut length = 0u64;
+		while length < max_walk_length && !is_distinguished(&key, config.distinguished_bits) {
+			let step = shared.steps.fetch_add(1, Ordering::Relaxed);
+			config.budget.check(step)?;
+			if O::ENABLED && step > 0 && step.is_multiple_of(shared.interval) {
+				let distinguished_points = shared.distinguished.load(Ordering::Relaxed);
+				let progress = Progress { iterations: step, seed, distinguished_points };
+				shared.observer.lock().unwrap().on_progress(&progress);
+			}
+			(x_i, a_i, b_i) = walker.step(&x_i, &a_i, &b_i);
+			key = group.encode(&x_i);
+			length += 1;
+		}
+		steps += length;
+		if shared.found.load(Ordering::Relaxed) {
+			return Ok(None)
+		}
+		if length == max_walk_length {
+			continue
+		}
+		let mut table = shared.table.lock().unwrap();
+		match table.get(&key) {
+			Some((a_j, b_j)) =>
+				if let Ok(key) = walker.solve(a_j, b_j, &a_i, &b_i) {
+					return Ok(Some(key))
+				},
+			None => {
+				table.insert(key, (a_i, b_i));
+				shared.distinguished.fetch_add(1, Ordering::Relaxed);
+			},
+		}
+	}
+	Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::integer::BigInteger;
+
+	#[test]
+	fn test_parallel_rho() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		let config = ParallelConfig {
+			threads: 4,
+			distinguished_bits: 2,
+			walk: Walk::Adding { r: 20 },
+			..Default::default()
+		};
+		for i in 0..20 {
+			let num = Integer::from(i * 9 + 5);
+			let y = two.modpow(&num, &p);
+			let key = parallel_rho(&config, &Integer::from(i), &two, &y, &p, &n)
+				.expect("Parallel rho should find the key!");
+			assert_eq!(key, num, "The found key {} is not the original key {}", key, num);
+		}
+	}
+}
@@ -0,0 +1,237 @@
+// Pohlig-Hellman reduction of the DLP to the prime-order subgroups.
+// Source: Handbook of Applied Cryptography, section 3.6.4.
+use crate::factor::{factorize_with, FactorConfig};
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::{Group, MultiplicativeGroup, Subgroup};
+use crate::integer::{BigInteger, Integer};
+use crate::utils::crt;
+use crate::validate::validate;
+use crate::{discrete_log_report_in, SolverConfig};
+use std::time::Instant;
+
+/// Number of seeds tried by the rho solver in each prime-order subgroup.
+pub(crate) const RHO_RESTARTS: usize = 32;
+
+/// Adds the counters of a subgroup solver to the statistics of the whole run.
+fn accumulate(total: &mut SolveReport, report: &SolveReport) {
+	total.iterations += report.iterations;
+	total.group_operations += report.group_operations;
+	total.restarts += report.restarts;
+	total.fruitless_cycles += report.fruitless_cycles;
+}
+
+/// Discrete log of `y` to the `base` of prime order `q`, its statistics are added to `total`.
+fn prime_order_log<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	q: &Integer,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	total: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let subgroup = Subgroup::new(group, q.clone());
+	if group.equal(y, &group.identity()) {
+		return Ok(Integer::new())
+	}
+	match discrete_log_report_in(&subgroup, config, seed, base, y) {
+		Ok((key, report)) => {
+			accumulate(total, &report);
+			Ok(key)
+		},
+		Err(RhoError::Cancelled(reason, partial)) => {
+			accumulate(total, &partial);
+			Err(reason.into_error(total.clone()))
+		},
+		Err(err) => Err(err),
+	}
+}
+
+/// Discrete log of `y` in the subgroup of order `q^e` generated by `base`,
+/// recovered one base-q digit at a time.
+#[allow(clippy::too_many_arguments)]
+fn prime_power_log<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	q: &Integer,
+	e: u32,
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+	total: &mut SolveReport,
+) -> RhoResult<Integer> {
+	let q_e = q.pow_u(e);
+	// gamma = base^(q^(e-1)) has order q.
+	let gamma = group.pow(base, &q.pow_u(e - 1));
+	let mut x = Integer::new();
+	let mut q_k = Integer::from(1);
+	for k in 0..e {
+		// h_k = (base^(-x) * y)^(q^(e-1-k))
+		let base_inv_x = group.pow(base, &Integer::from(&q_e - &x));
+		let h_k = group.pow(&group.op(&base_inv_x, y), &q.pow_u(e - 1 - k));
+		let d_k = prime_order_log(group, config, q, seed, &gamma, &h_k, total)?;
+		x += d_k * &q_k;
+		q_k *= q;
+	}
+	Ok(x)
+}
+
+/// Solves the DLP in any [`Group`] whose order `n` is given by its prime factorization.
+/// Each prime-power subgroup is solved separately and the results are combined by CRT.
+/// # Arguments
+/// * `group` - Group over which DLP is generated, `group.order()` must match `factors`.
+/// * `config` - Solver settings of the prime-order subgroups, the budget applies to each of them.
+/// * `factors` - Prime factorization of the group order as (prime, exponent) pairs.
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x.
+pub fn pohlig_hellman_in<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	factors: &[(Integer, u32)],
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<Integer> {
+	pohlig_hellman_report_in(group, config, factors, seed, base, y).map(|(key, _)| key)
+}
+
+/// Same as [`pohlig_hellman_in`] but also returns the statistics summed over the subgroups.
+pub fn pohlig_hellman_report_in<G: Group>(
+	group: &G,
+	config: &SolverConfig,
+	factors: &[(Integer, u32)],
+	seed: &Integer,
+	base: &G::Element,
+	y: &G::Element,
+) -> RhoResult<(Integer, SolveReport)> {
+	let start = Instant::now();
+	let mut total = SolveReport::default();
+	let n = group.order();
+	let mut congruences = Vec::with_capacity(factors.len());
+	for (q, e) in factors {
+		let q_e = q.pow_u(*e);
+		let cofactor = Integer::from(n / &q_e);
+		let base_q = group.pow(base, &cofactor);
+		let y_q = group.pow(y, &cofactor);
+		let x_q = prime_power_log(group, config, q, *e, seed, &base_q, &y_q, &mut total)?;
+		congruences.push((x_q, q_e));
+	}
+	let x = crt(&congruences).ok_or_else(|| {
+		RhoError::InvalidParameters(format!("factors do not match the group order {}", n))
+	})?;
+	total.elapsed = start.elapsed();
+	Ok((x, total))
+}
+
+/// Computes `x` = a mod n for the DLP base**x mod p == y when the order `n` is composite,
+/// e.g. `n = p - 1` for a generator of the whole group Z_p^*.
+/// # Arguments
+/// * `seed` - An big integer as mersenne twister pseudorandom generator seed.
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn pohlig_hellman(
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	let config = SolverConfig { restarts: RHO_RESTARTS, ..Default::default() };
+	pohlig_hellman_with(&config, seed, base, y, p, n)
+}
+
+/// Same as [`pohlig_hellman`] with the given solver settings of the prime-order subgroups.
+/// The budget of `config` also bounds the factorization of `n`.
+pub fn pohlig_hellman_with(
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	pohlig_hellman_report(config, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Same as [`pohlig_hellman_with`] but also returns the statistics summed over the subgroups.
+pub fn pohlig_hellman_report(
+	config: &SolverConfig,
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	let factor_config = FactorConfig { budget: config.budget.clone(), ..Default::default() };
+	let factors: Vec<(Integer, u32)> = factorize_with(n, &factor_config)?.into_iter().collect();
+	report_with_factors(config, &factors, seed, base, y, p, n)
+}
+
+/// Same as [`pohlig_hellman_with`] when the prime factorization of `n` is already known,
+/// e.g. from computing the order of `base`.
+/// # Arguments
+/// * `factors` - Prime factorization of `n` as (prime, exponent) pairs.
+pub fn pohlig_hellman_with_factors(
+	config: &SolverConfig,
+	factors: &[(Integer, u32)],
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<Integer> {
+	report_with_factors(config, factors, seed, base, y, p, n).map(|(key, _)| key)
+}
+
+/// Checks that `factors` multiply to `n` and solves the DLP with them.
+fn report_with_factors(
+	config: &SolverConfig,
+	factors: &[(Integer, u32)],
+	seed: &Integer,
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> RhoResult<(Integer, SolveReport)> {
+	validate(base, y, p, n)?;
+	let product = factors.iter().fold(Integer::from(1), |acc, (q, e)| acc * q.pow_u(*e));
+	if product != *n {
+		let reason = format!("factors do not match the group order {}", n);
+		return Err(RhoError::InvalidParameters(reason))
+	}
+	let group = MultiplicativeGroup::new(p.clone(), n.clone());
+	pohlig_hellman_report_in(&group, config, factors, seed, base, y)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_pohlig_hellman() {
+		// 3 generates Z_p^* for p = 2 * 3^3 * 5 * 17179869433 + 1.
+		let p = Integer::from(4638564746911u64);
+		let n = Integer::from(&p - 1);
+		let base = Integer::from(3);
+		for key in [0u64, 1, 1234567890123, 4638564746909] {
+			let key = Integer::from(key);
+			let y = base.modpow(&key, &p);
+			let res =
+				pohlig_hellman(&Integer::from(1), &base, &y, &p, &n).expect("DLP not solved!");
+			assert_eq!(res, key);
+		}
+		let config = SolverConfig::default();
+		let factors = [(2u64, 1u32), (3, 3), (5, 1), (17179869433, 1)];
+		let factors: Vec<(Integer, u32)> = factors.map(|(q, e)| (Integer::from(q), e)).to_vec();
+		let y = base.modpow(&Integer::from(987654321), &p);
+		let seed = Integer::new();
+		let res = pohlig_hellman_with_factors(&config, &factors, &seed, &base, &y, &p, &n);
+		assert_eq!(res, Ok(Integer::from(987654321)));
+		let res = pohlig_hellman_with_factors(&config, &factors[1..], &seed, &base, &y, &p, &n);
+		assert!(matches!(res, Err(RhoError::InvalidParameters(_))));
+	}
+}
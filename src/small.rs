@@ -0,0 +1,464 @@
+// Native-integer versions of the rho walk, BSGS and the collision equation for p < 2^64.
+// A product of two residues fits into a u128, so a step costs a native multiplication and
+// a 128-by-64 bit division instead of a GMP call and a heap allocation.
+use crate::bsgs::BsgsConfig;
+use crate::budget::{Budget, StopReason};
+use crate::generic::{RhoError, RhoResult, SolveReport, ValidationError};
+use crate::group::MultiplicativeGroup;
+use crate::integer::{seeded_rand, Integer};
+use crate::observer::NoObserver;
+use crate::utils::gen_bigint_range;
+use crate::walk::{Walk, Walker};
+use crate::{check_order, try_rho, CycleDetection, RhoConfig, MAX_CANDIDATES};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// The subgroup of order `n` of Z_p^* for a modulus `p < 2^64`, on native integers.
+/// The solvers on it expect the same valid instances as their `rug` counterparts, see
+/// [`crate::validate::validate`], and give the same results for the same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmallGroup {
+	p: u64,
+	n: u64,
+}
+
+impl SmallGroup {
+	/// `None` if `p` or `n` does not fit into a u64.
+	pub fn new(p: &Integer, n: &Integer) -> Option<Self> {
+		Some(SmallGroup { p: p.to_u64()?, n: n.to_u64()? })
+	}
+
+	/// Modulus of the group.
+	pub fn modulus(&self) -> u64 {
+		self.p
+	}
+
+	/// Order of the subgroup the DLP lives in.
+	pub fn order(&self) -> u64 {
+		self.n
+	}
+
+	/// `a * b mod p`.
+	pub fn mul(&self, a: u64, b: u64) -> u64 {
+		(a as u128 * b as u128 % self.p as u128) as u64
+	}
+
+	/// `a^k mod p`.
+	pub fn pow(&self, a: u64, mut k: u64) -> u64 {
+		let mut res = 1 % self.p;
+		let mut square = a % self.p;
+		while k > 0 {
+			if k & 1 == 1 {
+				res = self.mul(res, square);
+			}
+			square = self.mul(square, square);
+			k >>= 1;
+		}
+		res
+	}
+
+	/// Same as [`crate::eqs_solvers_in`]: the solution of the collision equation
+	///     (b1 - b2)*x = (a2 - a1) (mod n)
+	/// which satisfies base^x == y, for exponents in [0, n).
+	pub fn eqs_solvers(
+		&self,
+		base: u64,
+		y: u64,
+		a1: u64,
+		b1: u64,
+		a2: u64,
+		b2: u64,
+	) -> RhoResult<u64> {
+		if b1 == b2 {
+			return Err(RhoError::DegenerateCollision)
+		}
+		let (first, step, count) = solutions(a1, b1, a2, b2, self.n);
+		if count > MAX_CANDIDATES as u64 {
+			return Err(RhoError::NonInvertibleCollision)
+		}
+		(0..count)
+			.map(|k| first + k * step)
+			.find(|&x| self.pow(base, x) == y)
+			.ok_or(RhoError::NonInvertibleCollision)
+	}
+
+	/// Same as [`crate::pollard_rho_report`] for `base` and `y` in this group.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		seed: &Integer,
+		base: u64,
+		y: u64,
+	) -> RhoResult<(u64, SolveReport)> {
+		let (res, report) = self.rho_attempt(config, seed, base, y);
+		res.map(|key| (key, report))
+	}
+
+	/// Same as [`crate::try_pollard_rho_report`] for `base` and `y` in this group.
+	/// [`RhoConfig::measure_cycle`] is ignored.
+	pub fn try_pollard_rho_report(
+		&self,
+		config: &RhoConfig,
+		limit: usize,
+		seed: &Integer,
+		base: u64,
+		y: u64,
+	) -> RhoResult<(u64, SolveReport)> {
+		let res = try_rho(config, limit, seed, &mut NoObserver, |config, seed, _| {
+			let (res, report) = self.rho_attempt(config, seed, base, y);
+			(res.map(Integer::from), report)
+		});
+		// the key is reduced modulo n, so it fits into a u64.
+		res.map(|(key, report)| (key.to_u64_wrapping(), report))
+	}
+
+	/// Same as [`crate::bsgs::bsgs`] for `base` and `y` in this group.
+	pub fn bsgs(&self, config: &BsgsConfig, base: u64, y: u64) -> RhoResult<u64> {
+		self.bsgs_report(config, base, y).map(|(key, _)| key)
+	}
+
+	/// Same as [`crate::bsgs::bsgs_report`] for `base` and `y` in this group.
+	pub fn bsgs_report(
+		&self,
+		config: &BsgsConfig,
+		base: u64,
+		y: u64,
+	) -> RhoResult<(u64, SolveReport)> {
+		let n = self.n;
+		check_order(&Integer::from(n))?;
+		if config.max_table == 0 {
+			return Err(RhoError::InvalidParameters("BSGS table bound must be positive".into()))
+		}
+		// the balanced table has at most 2^32 entries, so it fits into u64.
+		let m = BsgsConfig::optimal_table(&Integer::from(n)).to_u64().unwrap();
+		let m = m.min(config.max_table);
+		let start = Instant::now();
+		let mut iterations = 0;
+		let report = |iterations: u64| SolveReport {
+			iterations,
+			group_operations: iterations,
+			elapsed: start.elapsed(),
+			..Default::default()
+		};
+		let stopped = |reason: StopReason, iterations: u64| reason.into_error(report(iterations));
+		let mut table = HashMap::new();
+		let mut baby = 1;
+		for j in 0..m {
+			config.budget.check(iterations).map_err(|reason| stopped(reason, iterations))?;
+			iterations += 1;
+			table.entry(baby).or_insert(j);
+			baby = self.mul(baby, base);
+		}
+		// giant = base^(-m) = base^(n - m), m <= n for every n >= 2.
+		let giant = self.pow(base, n - m);
+		let giant_steps = n.div_ceil(m);
+		let mut gamma = y;
+		for i in 0..giant_steps {
+			config.budget.check(iterations).map_err(|reason| stopped(reason, iterations))?;
+			iterations += 1;
+			if let Some(&j) = table.get(&gamma) {
+				let key = ((m as u128 * i as u128 + j as u128) % n as u128) as u64;
+				return Ok((key, report(iterations)))
+			}
+			gamma = self.mul(gamma, giant);
+		}
+		Err(ValidationError::TargetNotInSubgroup.into())
+	}
+
+	/// A single walk from the seed, the same as [`crate::rho_attempt`] without checkpoints.
+	fn rho_attempt(
+		&self,
+		config: &RhoConfig,
+		seed: &Integer,
+		base: u64,
+		y: u64,
+	) -> (RhoResult<u64>, SolveReport) {
+		let start = Instant::now();
+		let mut report = SolveReport::default();
+		let n = Integer::from(self.n);
+		if let Err(err) = check_order(&n) {
+			return (Err(err), report)
+		}
+		// draw the same random numbers as the walk on rug integers.
+		let mut rand = seeded_rand(seed);
+		let a = gen_bigint_range(&mut rand, &Integer::ZERO, &n).to_u64_wrapping();
+		let b = gen_bigint_range(&mut rand, &Integer::ZERO, &n).to_u64_wrapping();
+		let group = MultiplicativeGroup::new(Integer::from(self.p), n);
+		let (big_base, big_y) = (Integer::from(base), Integer::from(y));
+		let (walk, negation_map) = (config.walk, config.negation_map);
+		let walker = match Walker::new(walk, negation_map, &mut rand, &group, &big_base, &big_y) {
+			Ok(walker) => walker,
+			Err(err) => return (Err(err), report),
+		};
+		let walk = SmallWalk::new(*self, &walker, base, y);
+		let x = self.mul(self.pow(base, a), self.pow(y, b));
+		let budget = &config.budget;
+		let mut res = match config.cycle {
+			CycleDetection::Floyd => walk.floyd((x, a, b), budget, &mut report),
+			CycleDetection::Brent => walk.brent((x, a, b), budget, &mut report),
+		};
+		report.elapsed = start.elapsed();
+		if let Err(RhoError::Cancelled(_, partial)) = &mut res {
+			**partial = report.clone();
+		}
+		(res, report)
+	}
+}
+
+/// The smallest solution in [0, n) of (b1 - b2)*x = (a2 - a1) (mod n), the distance
+/// between two solutions and their number, see [`crate::eqs_solutions`].
+fn solutions(a1: u64, b1: u64, a2: u64, b2: u64, n: u64) -> (u64, u64, u64) {
+	let r = sub_mod(b1, b2, n);
+	if r == 0 {
+		return (0, 0, 0)
+	}
+	let dif = sub_mod(a2, a1, n);
+	let div = gcd(r, n);
+	if !dif.is_multiple_of(div) {
+		return (0, 0, 0)
+	}
+	let step = n / div;
+	match invert(r / div, step) {
+		Some(inv) => (((inv as u128 * (dif / div) as u128) % step as u128) as u64, step, div),
+		None => (0, 0, 0),
+	}
+}
+
+/// `(a + b) mod n` for `a` and `b` in [0, n).
+fn add_mod(a: u64, b: u64, n: u64) -> u64 {
+	let (sum, carry) = a.overflowing_add(b);
+	if carry || sum >= n {
+		sum.wrapping_sub(n)
+	} else {
+		sum
+	}
+}
+
+/// `(a - b) mod n` for `a` and `b` in [0, n).
+fn sub_mod(a: u64, b: u64, n: u64) -> u64 {
+	if a >= b {
+		a - b
+	} else {
+		n - (b - a)
+	}
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+	while b != 0 {
+		(a, b) = (b, a % b);
+	}
+	a
+}
+
+/// Inverse of `a` modulo `m` by the extended Euclidean algorithm.
+fn invert(a: u64, m: u64) -> Option<u64> {
+	let (mut r0, mut r1) = (m as i128, (a % m) as i128);
+	let (mut t0, mut t1) = (0i128, 1i128);
+	while r1 != 0 {
+		let q = r0 / r1;
+		(r0, r1) = (r1, r0 - q * r1);
+		(t0, t1) = (t1, t0 - q * t1);
+	}
+	(r0 == 1).then(|| t0.rem_euclid(m as i128) as u64)
+}
+
+/// The walk of a [`Walker`] on native integers.
+struct SmallWalk {
+	group: SmallGroup,
+	walk: Walk,
+	partitions: u64,
+	base: u64,
+	y: u64,
+	/// The multipliers `base^m * y^n` together with `m` and `n`.
+	multipliers: Vec<(u64, u64, u64)>,
+}
+
+impl SmallWalk {
+	fn new(group: SmallGroup, walker: &Walker<MultiplicativeGroup>, base: u64, y: u64) -> Self {
+		let multipliers = walker
+			.multipliers()
+			.map(|(value, m, n)| {
+				(value.to_u64_wrapping(), m.to_u64_wrapping(), n.to_u64_wrapping())
+			})
+			.collect();
+		SmallWalk {
+			group,
+			walk: walker.walk(),
+			partitions: walker.partitions() as u64,
+			base,
+			y,
+			multipliers,
+		}
+	}
+
+	/// The same step as [`Walker::step`].
+	fn step(&self, (x, a, b): (u64, u64, u64)) -> (u64, u64, u64) {
+		let group = &self.group;
+		let n = group.n;
+		let partition = x % self.partitions;
+		let multiplier = match self.walk {
+			// the partitions of func_f, func_g and func_h.
+			Walk::Pollard => match partition {
+				0 => None,
+				1 => return (group.mul(self.base, x), add_mod(a, 1, n), b),
+				_ => return (group.mul(self.y, x), a, add_mod(b, 1, n)),
+			},
+			_ => self.multipliers.get(partition as usize),
+		};
+		match multiplier {
+			Some(&(value, m, k)) => (group.mul(x, value), add_mod(a, m, n), add_mod(b, k, n)),
+			None => (group.mul(x, x), add_mod(a, a, n), add_mod(b, b, n)),
+		}
+	}
+
+	fn solve(&self, a1: u64, b1: u64, a2: u64, b2: u64) -> RhoResult<u64> {
+		self.group.eqs_solvers(self.base, self.y, a1, b1, a2, b2)
+	}
+
+	fn floyd(
+		&self,
+		start: (u64, u64, u64),
+		budget: &Budget,
+		report: &mut SolveReport,
+	) -> RhoResult<u64> {
+		let (mut single, mut double) = (start, start);
+		while self.group.n > report.iterations {
+			budget
+				.check(report.iterations)
+				.map_err(|reason| reason.into_error(SolveReport::default()))?;
+			single = self.step(single);
+			double = self.step(self.step(double));
+			report.iterations += 1;
+			report.group_operations += 3;
+			if single.0 == double.0 {
+				report.collision = Some((report.iterations, report.iterations * 2));
+				return self.solve(single.1, single.2, double.1, double.2)
+			}
+		}
+		Err(RhoError::IterationLimit)
+	}
+
+	fn brent(
+		&self,
+		start: (u64, u64, u64),
+		budget: &Budget,
+		report: &mut SolveReport,
+	) -> RhoResult<u64> {
+		let (mut current, mut saved) = (start, start);
+		let limit = self.group.n.saturating_mul(3);
+		let (mut power, mut lam) = (1u64, 0u64);
+		while limit > report.iterations {
+			budget
+				.check(report.iterations)
+				.map_err(|reason| reason.into_error(SolveReport::default()))?;
+			current = self.step(current);
+			lam += 1;
+			report.iterations += 1;
+			report.group_operations += 1;
+			if current.0 == saved.0 {
+				report.collision = Some((report.iterations - lam, report.iterations));
+				report.cycle = Some(lam);
+				return self.solve(saved.1, saved.2, current.1, current.2)
+			}
+			if lam == power {
+				saved = current;
+				power *= 2;
+				lam = 0;
+			}
+		}
+		Err(RhoError::IterationLimit)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::integer::BigInteger;
+	use crate::bsgs::bsgs_in;
+	use crate::{eqs_solvers_in, pollard_rho_report_in, try_pollard_rho_report_in};
+
+	#[test]
+	fn test_arithmetic() {
+		let p = u64::MAX - 58;
+		let group = SmallGroup { p, n: p - 1 };
+		let big_p = Integer::from(p);
+		for (a, k) in [(2, p - 2), (p - 1, 3), (123_456_789_123, 987_654_321)] {
+			let expected = Integer::from(a).modpow(&Integer::from(k), &big_p);
+			assert_eq!(group.pow(a, k), expected);
+		}
+		assert_eq!(group.mul(p - 1, p - 1), 1);
+		assert_eq!(add_mod(u64::MAX - 59, u64::MAX - 59, u64::MAX - 58), u64::MAX - 60);
+		assert_eq!(invert(3, 7), Some(5));
+		assert_eq!(invert(4, 12), None);
+	}
+
+	#[test]
+	fn test_eqs_solvers() {
+		// 5 generates Z_383^* of order 382, so 4*x = 8 (mod 382) has the solutions 2 and 193.
+		let big = MultiplicativeGroup::new(Integer::from(383), Integer::from(382));
+		let group = SmallGroup { p: 383, n: 382 };
+		let five = Integer::from(5);
+		for key in [2, 193] {
+			let y = five.modpow(&Integer::from(key), &Integer::from(383));
+			let [a1, b1, a2, b2] = [3, 5, 11, 1].map(Integer::from);
+			let expected = eqs_solvers_in(&big, &five, &y, &a1, &b1, &a2, &b2);
+			let y = y.to_u64().unwrap();
+			assert_eq!(group.eqs_solvers(5, y, 3, 5, 11, 1).map(Integer::from), expected);
+			assert_eq!(group.eqs_solvers(5, y, 3, 5, 11, 5), Err(RhoError::DegenerateCollision));
+		}
+	}
+
+	#[test]
+	fn test_same_as_rug() {
+		let p = Integer::from(1_000_000_007u64);
+		let n = Integer::from(500_000_003u64);
+		let four = Integer::from(4);
+		let y = four.modpow(&Integer::from(123_456_789), &p);
+		let big = MultiplicativeGroup::new(p.clone(), n.clone());
+		let group = SmallGroup::new(&p, &n).unwrap();
+		let small_y = y.to_u64().unwrap();
+		for walk in [Walk::Pollard, Walk::Adding { r: 20 }, Walk::Mixed { r: 16, squarings: 4 }] {
+			for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+				let config = RhoConfig { cycle, walk, ..Default::default() };
+				let seed = Integer::from(1);
+				let expected = pollard_rho_report_in(&big, &config, &seed, &four, &y);
+				let res = group.pollard_rho_report(&config, &seed, 4, small_y);
+				match (expected, res) {
+					(Ok((key, expected)), Ok((found, report))) => {
+						assert_eq!(found, key);
+						assert_eq!(report.iterations, expected.iterations);
+						assert_eq!(report.collision, expected.collision);
+						assert_eq!(report.cycle, expected.cycle);
+					},
+					(expected, res) => assert_eq!(res.err(), expected.err()),
+				}
+				let expected = try_pollard_rho_report_in(&big, &config, 10, &seed, &four, &y);
+				let res = group.try_pollard_rho_report(&config, 10, &seed, 4, small_y);
+				let (key, expected) = expected.unwrap();
+				let (found, report) = res.unwrap();
+				assert_eq!(found, key);
+				assert_eq!(report.restarts, expected.restarts);
+				assert_eq!(report.iterations, expected.iterations);
+			}
+		}
+	}
+
+	#[test]
+	fn test_bsgs() {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let big = MultiplicativeGroup::new(p.clone(), n.clone());
+		let group = SmallGroup::new(&p, &n).unwrap();
+		for config in [BsgsConfig::default(), BsgsConfig { max_table: 4, ..Default::default() }] {
+			for key in 0..191u64 {
+				let y = group.pow(2, key);
+				assert_eq!(group.bsgs(&config, 2, y), Ok(key));
+				let expected = bsgs_in(&big, &config, &Integer::from(2), &Integer::from(y));
+				assert_eq!(expected, Ok(Integer::from(key)));
+			}
+		}
+		let config = BsgsConfig { max_table: 0, ..Default::default() };
+		assert!(matches!(group.bsgs(&config, 2, 2), Err(RhoError::InvalidParameters(_))));
+	}
+}
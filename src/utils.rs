@@ -1,10 +1,24 @@
-use rug::{rand::RandState, Integer};
+use crate::integer::{BigInteger, Integer, RandState};
 
 /// These real versions are due to Kaisuki, 2021/01/07 added
 /// modified by yangfh2004, 2022/01/31
-
 pub fn gen_bigint_range(rand: &mut RandState, start: &Integer, stop: &Integer) -> Integer {
 	let range = Integer::from(stop - start);
-	let below = range.random_below(rand);
+	let below = range.sample_below(rand);
 	start + below
-}
\ No newline at end of file
+}
+
+/// Chinese remainder theorem for pairwise coprime moduli.
+/// Returns the unique `x` in [0, m_1 * m_2 * ...) with `x = r_i (mod m_i)` for every (r_i, m_i).
+pub fn crt(congruences: &[(Integer, Integer)]) -> Option<Integer> {
+	let mut x = Integer::new();
+	let mut modulus = Integer::from(1);
+	for (r, m) in congruences {
+		let inv = modulus.modinv(m)?;
+		let t = Integer::from(r - &x) * inv;
+		let t = t.rem_euclid(m);
+		x += t * &modulus;
+		modulus *= m;
+	}
+	Some(x)
+}
@@ -0,0 +1,125 @@
+// Validation of DLP instances over Z_p^*.
+// Source: Handbook of Applied Cryptography, algorithm 4.79.
+use crate::generic::ValidationError;
+use crate::integer::{BigInteger, Integer};
+
+/// Number of Miller-Rabin rounds used for the primality checks.
+const PRIME_REPS: u32 = 30;
+
+fn is_one(base: &Integer, exp: &Integer, p: &Integer) -> bool {
+	base.modpow(exp, p) == 1
+}
+
+/// Check every precondition of the DLP base^x = y (mod p) in the subgroup of order `n`
+/// and report the first one which fails.
+/// The subgroup of order `n` of the cyclic group Z_p^* is unique, hence `y^n = 1 (mod p)`
+/// proves `y` lies in the subgroup generated by `base` once `base` has order exactly `n`.
+/// That is verified for a prime `n`, use [`order_of`] to compute the order for a composite one.
+/// # Arguments
+/// * `base` - Generator of the group.
+/// * `y` - Result of base**x mod p.
+/// * `p` - Group over which DLP is generated.
+/// * `n` - Order of the group generated by `base`.
+pub fn validate(
+	base: &Integer,
+	y: &Integer,
+	p: &Integer,
+	n: &Integer,
+) -> Result<(), ValidationError> {
+	if !p.is_probable_prime(PRIME_REPS) {
+		return Err(ValidationError::ModulusNotPrime)
+	}
+	if *base < 1 || base >= p {
+		return Err(ValidationError::BaseOutOfRange)
+	}
+	if *y < 1 || y >= p {
+		return Err(ValidationError::TargetOutOfRange)
+	}
+	if *n < 1 || !Integer::from(p - 1).is_divisible(n) {
+		return Err(ValidationError::OrderNotDividing)
+	}
+	let n_is_prime = n.is_probable_prime(PRIME_REPS);
+	if !is_one(base, n, p) || (n_is_prime && *base == 1) {
+		return Err(ValidationError::BaseOrderMismatch)
+	}
+	if !is_one(y, n, p) {
+		return Err(ValidationError::TargetNotInSubgroup)
+	}
+	Ok(())
+}
+
+/// Computes the multiplicative order of `base` modulo the prime `p`
+/// from the factorization of `p - 1`.
+/// # Arguments
+/// * `base` - Element whose order is computed.
+/// * `p` - Prime modulus.
+/// * `factors` - Prime factorization of `p - 1` as (prime, exponent) pairs.
+pub fn order_of(
+	base: &Integer,
+	p: &Integer,
+	factors: &[(Integer, u32)],
+) -> Result<Integer, ValidationError> {
+	if *base < 1 || base >= p {
+		return Err(ValidationError::BaseOutOfRange)
+	}
+	let group_order = Integer::from(p - 1);
+	let mut product = Integer::from(1);
+	for (q, e) in factors {
+		if !q.is_probable_prime(PRIME_REPS) {
+			return Err(ValidationError::FactorizationMismatch)
+		}
+		product *= q.pow_u(*e);
+	}
+	if product != group_order {
+		return Err(ValidationError::FactorizationMismatch)
+	}
+	let mut order = group_order;
+	for (q, e) in factors {
+		order /= q.pow_u(*e);
+		let mut g1 = base.modpow(&order, p);
+		while g1 != 1 {
+			g1 = g1.modpow(q, p);
+			order *= q;
+		}
+	}
+	Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_validate() {
+		let (p, n) = (Integer::from(383), Integer::from(191));
+		let two = Integer::from(2);
+		let y = two.modpow(&Integer::from(57), &p);
+		assert_eq!(validate(&two, &y, &p, &n), Ok(()));
+		let err = |base: i32, y: i32, p: i32, n: i32| {
+			validate(&Integer::from(base), &Integer::from(y), &Integer::from(p), &Integer::from(n))
+				.unwrap_err()
+		};
+		assert_eq!(err(2, 4, 385, 191), ValidationError::ModulusNotPrime);
+		assert_eq!(err(383, 4, 383, 191), ValidationError::BaseOutOfRange);
+		assert_eq!(err(2, 0, 383, 191), ValidationError::TargetOutOfRange);
+		assert_eq!(err(2, 4, 383, 190), ValidationError::OrderNotDividing);
+		// 5 generates the whole group of order 382.
+		assert_eq!(err(5, 4, 383, 191), ValidationError::BaseOrderMismatch);
+		assert_eq!(err(1, 1, 383, 191), ValidationError::BaseOrderMismatch);
+		assert_eq!(err(2, 5, 383, 191), ValidationError::TargetNotInSubgroup);
+	}
+
+	#[test]
+	fn test_order_of() {
+		let p = Integer::from(383);
+		let factors = [(Integer::from(2), 1), (Integer::from(191), 1)];
+		assert_eq!(order_of(&Integer::from(2), &p, &factors), Ok(Integer::from(191)));
+		assert_eq!(order_of(&Integer::from(5), &p, &factors), Ok(Integer::from(382)));
+		assert_eq!(order_of(&Integer::from(382), &p, &factors), Ok(Integer::from(2)));
+		assert_eq!(order_of(&Integer::from(1), &p, &factors), Ok(Integer::from(1)));
+		assert_eq!(
+			order_of(&Integer::from(2), &p, &factors[..1]),
+			Err(ValidationError::FactorizationMismatch)
+		);
+	}
+}
@@ -0,0 +1,339 @@
+// Iteration functions of the pseudo-random walk.
+// Source: E. Teske, "On random walks for Pollard's rho method",
+//         Mathematics of Computation 70 (2001), 809-825.
+use crate::generic::{RhoError, RhoResult, SolveReport};
+use crate::group::Group;
+use crate::integer::{BigInteger, Integer, RandState};
+use crate::utils::gen_bigint_range;
+use crate::{eqs_solvers_in, func_f, func_g, func_h};
+use std::collections::BTreeMap;
+
+/// Iteration function of the rho walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Walk {
+	/// Pollard's original walk with 3 partitions: square, multiply by `base` or by `y`.
+	#[default]
+	Pollard,
+	/// Teske's r-adding walk, multiply by one of `r` precomputed `base^m_j * y^n_j`.
+	/// `r` around 20 behaves close to a truly random walk.
+	Adding { r: usize },
+	/// Teske's mixed walk, `r` multipliers plus `squarings` partitions which square the point.
+	Mixed { r: usize, squarings: usize },
+}
+
+/// Fruitless cycles of the negation map longer than this are not escaped from.
+const MAX_FRUITLESS_CYCLE: u64 = 64;
+
+/// Precomputed multiplier `base^m * y^n` of the adding and mixed walks.
+struct Multiplier<E> {
+	value: E,
+	m: Integer,
+	n: Integer,
+}
+
+/// Fruitless cycles a walk has escaped from, with the exponents of their smallest point.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Escapes(BTreeMap<Integer, (Integer, Integer)>);
+
+impl Escapes {
+	/// Encoding of the smallest point of every cycle with its exponents, ordered by the encoding.
+	pub fn iter(&self) -> impl Iterator<Item = (&Integer, &Integer, &Integer)> {
+		self.0.iter().map(|(key, (a, b))| (key, a, b))
+	}
+
+	/// Whether the walk has not escaped from any cycle yet.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl FromIterator<(Integer, Integer, Integer)> for Escapes {
+	fn from_iter<I: IntoIterator<Item = (Integer, Integer, Integer)>>(iter: I) -> Self {
+		Escapes(iter.into_iter().map(|(key, a, b)| (key, (a, b))).collect())
+	}
+}
+
+/// How a walk goes on after it met a fruitless cycle.
+pub(crate) enum Escape<E> {
+	/// Continue at this point outside of the cycle.
+	Continue(E, Integer, Integer),
+	/// The walk came back to an earlier cycle, solve base^a1 * y^b1 == base^a2 * y^b2.
+	Collision(Integer, Integer, Integer, Integer),
+}
+
+/// The walk bound to a particular DLP instance, with all multipliers precomputed.
+pub(crate) struct Walker<'a, G: Group> {
+	walk: Walk,
+	multipliers: Vec<Multiplier<G::Element>>,
+	partitions: u32,
+	/// Walk on the classes {x, x^-1} instead of the elements.
+	negation_map: bool,
+	group: &'a G,
+	base: &'a G::Element,
+	y: &'a G::Element,
+}
+
+impl<'a, G: Group> Walker<'a, G> {
+	/// Draw the random exponents of the multipliers from `rand`.
+	/// Fails if the walk has no partition at all, or if the negation map is asked for
+	/// in a group without [`Group::inverse`].
+	pub(crate) fn new(
+		walk: Walk,
+		negation_map: bool,
+		rand: &mut RandState,
+		group: &'a G,
+		base: &'a G::Element,
+		y: &'a G::Element,
+	) -> RhoResult<Walker<'a, G>> {
+		let (r, squarings) = match walk {
+			// the three partitions are handled by func_f, func_g and func_h.
+			Walk::Pollard => (0, 3),
+			Walk::Adding { r } => (r, 0),
+			Walk::Mixed { r, squarings } => (r, squarings),
+		};
+		let partitions = u32::try_from(r + squarings)
+			.ok()
+			.filter(|&k| k > 0)
+			.ok_or_else(|| RhoError::InvalidParameters(format!("{:?} has no partition", walk)))?;
+		if negation_map && group.inverse(base).is_none() {
+			return Err(RhoError::InvalidParameters("the group has no negation map".into()))
+		}
+		let zero = Integer::new();
+		let n = group.order();
+		let mut multipliers = Vec::with_capacity(r);
+		for _ in 0..r {
+			let m = gen_bigint_range(rand, &zero, n);
+			let k = gen_bigint_range(rand, &zero, n);
+			let value = group.op(&group.pow(base, &m), &group.pow(y, &k));
+			multipliers.push(Multiplier { value, m, n: k });
+		}
+		Ok(Walker { walk, multipliers, partitions, negation_map, group, base, y })
+	}
+
+	/// Group the walk runs in.
+	pub(crate) fn group(&self) -> &'a G {
+		self.group
+	}
+
+	/// Generator of the group.
+	pub(crate) fn base(&self) -> &'a G::Element {
+		self.base
+	}
+
+	/// Element whose logarithm the walk searches.
+	pub(crate) fn y(&self) -> &'a G::Element {
+		self.y
+	}
+
+	/// Iteration function of the walk.
+	pub(crate) fn walk(&self) -> Walk {
+		self.walk
+	}
+
+	/// Number of partitions the encoding of a point is reduced modulo.
+	pub(crate) fn partitions(&self) -> u32 {
+		self.partitions
+	}
+
+	/// The multipliers `base^m * y^n` together with `m` and `n`.
+	pub(crate) fn multipliers(&self) -> impl Iterator<Item = (&G::Element, &Integer, &Integer)> {
+		self.multipliers.iter().map(|mult| (&mult.value, &mult.m, &mult.n))
+	}
+
+	/// Solve the collision base^a1 * y^b1 == base^a2 * y^b2 of two walks.
+	pub(crate) fn solve(
+		&self,
+		a1: &Integer,
+		b1: &Integer,
+		a2: &Integer,
+		b2: &Integer,
+	) -> RhoResult<Integer> {
+		eqs_solvers_in(self.group, self.base, self.y, a1, b1, a2, b2)
+	}
+
+	/// Starting point `base^a * y^b` of a walk.
+	/// With the negation map the exponents must come from [`Walker::enter`].
+	pub(crate) fn start(&self, a: &Integer, b: &Integer) -> G::Element {
+		let group = self.group;
+		group.op(&group.pow(self.base, a), &group.pow(self.y, b))
+	}
+
+	/// Exponents of the walk starting at the class of `base^a * y^b`,
+	/// which are negated if its representative is the inverse.
+	pub(crate) fn enter(&self, a: Integer, b: Integer) -> (Integer, Integer) {
+		let x = self.start(&a, &b);
+		let ((_, a, b), _) = self.canonical((x, a, b));
+		(a, b)
+	}
+
+	/// Representative of the class {x, x^-1} with the smaller encoding, and whether it is
+	/// the inverse of `x`. Without the negation map every element is its own representative.
+	fn canonical(
+		&self,
+		(x, a, b): (G::Element, Integer, Integer),
+	) -> ((G::Element, Integer, Integer), bool) {
+		let group = self.group;
+		if !self.negation_map {
+			return ((x, a, b), false)
+		}
+		let inv = group.inverse(&x).expect("Group should have an inverse!");
+		if group.encode(&inv) >= group.encode(&x) {
+			return ((x, a, b), false)
+		}
+		let n = group.order();
+		let a = (-a).rem_euclid(n);
+		let b = (-b).rem_euclid(n);
+		((inv, a, b), true)
+	}
+
+	/// The representative of the class of `x^2`.
+	fn square(&self, x: &G::Element, a: &Integer, b: &Integer) -> (G::Element, Integer, Integer) {
+		let n = self.group.order();
+		self.canonical((
+			self.group.op(x, x),
+			Integer::from(a * 2).rem_euclid(n),
+			Integer::from(b * 2).rem_euclid(n),
+		))
+		.0
+	}
+
+	/// Whether the partition multiplies with a fixed element instead of squaring.
+	fn multiplies(&self, partition: u32) -> bool {
+		match self.walk {
+			Walk::Pollard => partition != 0,
+			_ => (partition as usize) < self.multipliers.len(),
+		}
+	}
+
+	/// Whether the collision of two walks with the same exponents is a fruitless cycle
+	/// of the negation map rather than a useless collision of the walk itself.
+	pub(crate) fn is_fruitless(
+		&self,
+		a1: &Integer,
+		b1: &Integer,
+		a2: &Integer,
+		b2: &Integer,
+	) -> bool {
+		self.negation_map && a1 == a2 && b1 == b2
+	}
+
+	/// Leaves the fruitless cycle through `x` by squaring its point with the smallest
+	/// encoding, so every walk trapped in the cycle continues at the same point.
+	/// A walk which comes back to a cycle it escaped from before usually got there on
+	/// another path, then the two exponents of its smallest point give the collision.
+	/// Fails for cycles longer than `MAX_FRUITLESS_CYCLE` and for walks which came back
+	/// on the same path.
+	pub(crate) fn escape(
+		&self,
+		escapes: &mut Escapes,
+		x: &G::Element,
+		a: &Integer,
+		b: &Integer,
+		report: &mut SolveReport,
+	) -> RhoResult<Escape<G::Element>> {
+		let group = self.group;
+		let mut smallest = (x.clone(), a.clone(), b.clone());
+		let mut current = self.step(x, a, b);
+		let mut length = 1;
+		while !group.equal(&current.0, x) {
+			if length == MAX_FRUITLESS_CYCLE {
+				return Err(RhoError::DegenerateCollision)
+			}
+			if group.encode(&current.0) < group.encode(&smallest.0) {
+				smallest = current.clone();
+			}
+			current = self.step(&current.0, &current.1, &current.2);
+			length += 1;
+		}
+		report.group_operations += length + 1;
+		let (point, a, b) = smallest;
+		match escapes.0.insert(group.encode(&point), (a.clone(), b.clone())) {
+			Some((a_0, b_0)) if a_0 == a && b_0 == b => Err(RhoError::DegenerateCollision),
+			Some((a_0, b_0)) => Ok(Escape::Collision(a_0, b_0, a, b)),
+			None => {
+				report.fruitless_cycles += 1;
+				let (x, a, b) = self.square(&point, &a, &b);
+				Ok(Escape::Continue(x, a, b))
+			},
+		}
+	}
+
+	/// One step of the walk, updates `x_i` together with its exponents `a_i` and `b_i`.
+	pub(crate) fn step(
+		&self,
+		x_i: &G::Element,
+		a_i: &Integer,
+		b_i: &Integer,
+	) -> (G::Element, Integer, Integer) {
+		let group = self.group;
+		let n = group.order();
+		let key = group.encode(x_i);
+		let partition = key.mod_u(self.partitions);
+		let next = if self.walk == Walk::Pollard {
+			let a_next = func_g(a_i, n, &key);
+			let b_next = func_h(b_i, n, &key);
+			let x_next = func_f(group, x_i, &key, self.base, self.y);
+			(x_next, a_next, b_next)
+		} else {
+			match self.multipliers.get(partition as usize) {
+				Some(mult) => (
+					group.op(x_i, &mult.value),
+					Integer::from(a_i + &mult.m).rem_euclid(n),
+					Integer::from(b_i + &mult.n).rem_euclid(n),
+				),
+				None => (
+					group.op(x_i, x_i),
+					Integer::from(a_i * 2).rem_euclid(n),
+					Integer::from(b_i * 2).rem_euclid(n),
+				),
+			}
+		};
+		let (next, inverted) = self.canonical(next);
+		// x_i -> (x_i * m)^-1 -> x_i is a fruitless 2-cycle whenever the representative is the
+		// inverse and falls into the same partition, leave it right away by squaring the
+		// smaller of both points, which gives the same point from either of them.
+		if inverted && self.multiplies(partition) {
+			let next_key = group.encode(&next.0);
+			if next_key.mod_u(self.partitions) == partition {
+				return if next_key < key {
+					self.square(&next.0, &next.1, &next.2)
+				} else {
+					self.square(x_i, a_i, b_i)
+				}
+			}
+		}
+		next
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{try_pollard_rho_with, CycleDetection, RhoConfig};
+
+	fn check_walk(walk: Walk) {
+		let p = Integer::from(383);
+		let n = Integer::from(191);
+		let two = Integer::from(2);
+		for cycle in [CycleDetection::Floyd, CycleDetection::Brent] {
+			let config = RhoConfig { cycle, walk, ..Default::default() };
+			for i in 0..50 {
+				let num = Integer::from((i * 11 + 1) % 191);
+				let y = two.modpow(&num, &p);
+				let key =
+					try_pollard_rho_with(&config, 10, &Integer::from(i), &two, &y, &p, &n).unwrap();
+				assert_eq!(key, num, "The found key {} is not the original key {}", key, num);
+			}
+		}
+	}
+
+	#[test]
+	fn test_adding_walk() {
+		check_walk(Walk::Adding { r: 20 });
+	}
+
+	#[test]
+	fn test_mixed_walk() {
+		check_walk(Walk::Mixed { r: 16, squarings: 4 });
+	}
+}